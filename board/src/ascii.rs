@@ -0,0 +1,47 @@
+use crate::{board::Coordinate, small::SmallRequest};
+
+/// Draw the board one line per row, the top row first.
+/// Heads are capital letters by snake id, A being id 0, and their bodies the same letter in lower case.
+/// Food is `*`, hazards without anything on them are `~` and empty cells `.`, dead snakes are left out.
+pub fn render(state: &SmallRequest) -> Vec<String> {
+    let width = state.board.width as usize;
+    let height = state.board.height as usize;
+    let mut grid = vec![vec!['.'; width]; height];
+    let mut put = |cell: &Coordinate, c: char| {
+        if cell.x >= 0 && cell.y >= 0 && (cell.x as usize) < width && (cell.y as usize) < height {
+            grid[height - 1 - cell.y as usize][cell.x as usize] = c;
+        }
+    };
+    for hazard in &state.board.hazards {
+        put(hazard, '~');
+    }
+    for food in &state.board.food {
+        put(food, '*');
+    }
+    for snake in &state.board.snakes {
+        if !snake.alive {
+            continue;
+        }
+        let letter = (b'a' + snake.id) as char;
+        for cell in snake.body.iter().rev() {
+            put(cell, letter);
+        }
+        put(&snake.head, letter.to_ascii_uppercase());
+    }
+    let mut out: Vec<String> = grid
+        .iter()
+        .enumerate()
+        .map(|(n, row)| {
+            let cells: Vec<String> = row.iter().map(|x| x.to_string()).collect();
+            format!("{:>2} {}", height - 1 - n, cells.join(" "))
+        })
+        .collect();
+    let columns: Vec<String> = (0..width).map(|x| (x % 10).to_string()).collect();
+    out.push(format!("   {}", columns.join(" ")));
+    out.push(format!(
+        "turn {}, you are {}",
+        state.turn,
+        (b'A' + state.you as u8) as char
+    ));
+    out
+}
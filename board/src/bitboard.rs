@@ -0,0 +1,52 @@
+/// Amount of bits per row, boards up to 11x11 fit into a u128
+pub const ROW: u32 = 11;
+
+/// Every cell of the largest board we can represent
+pub const FULL: u128 = (1 << (ROW * ROW)) - 1;
+
+/// The leftmost column
+const LEFT_COLUMN: u128 = {
+    let mut out = 0;
+    let mut y = 0;
+    while y < ROW {
+        out |= 1 << (y * ROW);
+        y += 1;
+    }
+    out
+};
+
+/// The rightmost column
+const RIGHT_COLUMN: u128 = LEFT_COLUMN << (ROW - 1);
+
+/// Bitboard of every cell inside a board of the given size
+pub fn board_mask(width: u8, height: u8) -> u128 {
+    let row = (1u128 << width.min(ROW as u8)) - 1;
+    let rows = LEFT_COLUMN & ((1 << (height.min(ROW as u8) as u32 * ROW)) - 1);
+    // a row is never wider than ROW bits, so multiplying copies it onto every row without carries
+    row * rows
+}
+
+/// Move every bit one cell up
+pub fn shift_up(bb: u128) -> u128 {
+    (bb << ROW) & FULL
+}
+
+/// Move every bit one cell down
+pub fn shift_down(bb: u128) -> u128 {
+    bb >> ROW
+}
+
+/// Move every bit one cell left, bits on the left edge fall off
+pub fn shift_left(bb: u128) -> u128 {
+    (bb & !LEFT_COLUMN) >> 1
+}
+
+/// Move every bit one cell right, bits on the right edge fall off
+pub fn shift_right(bb: u128) -> u128 {
+    (bb & !RIGHT_COLUMN) << 1
+}
+
+/// All cells that are next to a set bit
+pub fn neighbours(bb: u128) -> u128 {
+    shift_up(bb) | shift_down(bb) | shift_left(bb) | shift_right(bb)
+}
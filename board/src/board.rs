@@ -2,15 +2,102 @@ use std::ops::{Add, AddAssign};
 
 use serde::Deserialize;
 
-use crate::small::{SmallBattlesnake, SmallRequest};
+use crate::{
+    maps::GameMap,
+    rules::{GameMode, Rules},
+    small::{SmallBattlesnake, SmallRequest},
+};
 
 /// Input a game request
 #[derive(Clone, Debug, Deserialize)]
 pub struct GameRequest {
+    #[serde(default)]
+    game: GameInfo,
     pub turn: i64,
     board: BoardInfo,
     you: BattleSnake,
 }
+
+/// Info about the game being played
+#[derive(Clone, Debug, Deserialize)]
+struct GameInfo {
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    ruleset: Ruleset,
+    #[serde(default)]
+    map: String,
+    /// Milliseconds a move request may take, latency included
+    #[serde(default = "default_timeout")]
+    timeout: u64,
+}
+
+/// The ruleset of the game
+#[derive(Clone, Debug, Deserialize)]
+struct Ruleset {
+    name: String,
+    #[serde(default)]
+    settings: RulesetSettings,
+}
+
+/// Ruleset settings, missing ones fall back to the official defaults
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RulesetSettings {
+    #[serde(default = "default_food_spawn_chance")]
+    food_spawn_chance: u8,
+    #[serde(default = "default_minimum_food")]
+    minimum_food: u8,
+    #[serde(default = "default_hazard_damage")]
+    hazard_damage_per_turn: u8,
+}
+
+fn default_timeout() -> u64 {
+    500
+}
+
+fn default_food_spawn_chance() -> u8 {
+    Rules::default().food_spawn_chance
+}
+
+fn default_minimum_food() -> u8 {
+    Rules::default().minimum_food
+}
+
+fn default_hazard_damage() -> u8 {
+    Rules::default().hazard_damage
+}
+
+impl Default for GameInfo {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            ruleset: Ruleset::default(),
+            map: String::new(),
+            timeout: default_timeout(),
+        }
+    }
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Self {
+            name: "standard".to_string(),
+            settings: Default::default(),
+        }
+    }
+}
+
+impl Default for RulesetSettings {
+    fn default() -> Self {
+        let rules = Rules::default();
+        Self {
+            food_spawn_chance: rules.food_spawn_chance,
+            minimum_food: rules.minimum_food,
+            hazard_damage_per_turn: rules.hazard_damage,
+        }
+    }
+}
 /// The board info itself
 #[derive(Clone, Debug, Deserialize)]
 struct BoardInfo {
@@ -27,8 +114,6 @@ struct BattleSnake {
     id: String,
     health: u8,
     body: Vec<Coordinate>,
-    head: Coordinate,
-    length: u16,
 }
 
 /// A coordinate
@@ -67,6 +152,10 @@ impl Add for Coordinate {
 
 impl From<Coordinate> for u128 {
     fn from(input: Coordinate) -> Self {
+        // off the board, so it has no bit
+        if input.x < 0 || input.y < 0 || input.x >= 11 || input.y >= 11 {
+            return 0;
+        }
         let mut out = 1;
         out <<= input.y * 11;
         out <<= input.x;
@@ -85,34 +174,47 @@ impl From<u128> for Coordinate {
 }
 
 impl GameRequest {
+    /// Id of the game this request is from, empty if the request didn't say
+    pub fn game_id(&self) -> &str {
+        &self.game.id
+    }
+
+    /// Milliseconds the game gives to answer a move request, latency included
+    pub fn timeout(&self) -> u64 {
+        self.game.timeout
+    }
+
+    /// Ids of the snakes, in the same order as the ids given out by into_small
+    pub fn snake_ids(&self) -> Vec<&str> {
+        self.board.snakes.iter().map(|x| x.id.as_str()).collect()
+    }
+
     pub fn into_small(&self) -> SmallRequest {
         let mut out = SmallRequest::new();
         out.turn = self.turn;
+        let settings = &self.game.ruleset.settings;
+        out.rules = Rules {
+            mode: GameMode::from_name(&self.game.ruleset.name),
+            food_spawn_chance: settings.food_spawn_chance,
+            minimum_food: settings.minimum_food,
+            hazard_damage: settings.hazard_damage_per_turn,
+            map: GameMap::from_name(&self.game.map),
+        };
         out.board.height = self.board.height;
         out.board.width = self.board.width;
 
         out.board.food = self.board.food.clone();
+        for food in &self.board.food {
+            out.board.food_bb |= u128::from(*food);
+        }
         out.board.hazards = self.board.hazards.clone();
+        for hazard in &self.board.hazards {
+            out.board.hazard_bb |= u128::from(*hazard);
+        }
         for (x, y) in self.board.snakes.iter().enumerate() {
-            out.board.snakes.push(SmallBattlesnake {
-                id: x as u8,
-                health: y.health,
-                body: y.body.clone(),
-                head: y.head,
-                alive: true,
-                length: y.length,
-                head_bb: 0,
-                body_bb: 0,
-            });
-            // give the last added snake the body bits into its body bitboard
-            // exclude the head because reasons
-            for coord in &y.body[1..] {
-                out.board.snakes.last_mut().unwrap().body_bb |= u128::from(*coord);
-            }
-
-            // give the last added snake the head bit into its head bitboard
-            out.board.snakes.last_mut().unwrap().head_bb = u128::from(y.head);
-
+            out.board
+                .snakes
+                .push(SmallBattlesnake::new(x as u8, y.health, y.body.clone()));
             if y.id == self.you.id {
                 out.you = x;
             }
@@ -4,3 +4,18 @@ pub mod board;
 /// Small data types, Use these over the normal sized ones.
 /// It has a few special bits and bobs that make it a lot easier.
 pub mod small;
+
+/// Ruleset info that changes how the game plays out.
+pub mod rules;
+
+/// Official maps and how their hazards move over time.
+pub mod maps;
+
+/// Bitboard helpers for the 11 bit wide row layout used by the small types.
+pub mod bitboard;
+
+/// Short text form of positions, for the command line and tests.
+pub mod notation;
+
+/// Plain text pictures of positions, for printing while debugging.
+pub mod ascii;
@@ -0,0 +1,150 @@
+use crate::board::Coordinate;
+
+/// Turns between each new hazard on the spiral map
+const SPIRAL_EVERY: i64 = 3;
+
+/// Official game maps that place hazards
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameMap {
+    /// No hazards, also used for unknown maps, which are assumed to keep their hazards where they are
+    #[default]
+    Standard,
+    /// Hazards close in from a random side, so they can't be predicted
+    Royale,
+    /// A fixed maze where the hazards are walls
+    ArcadeMaze,
+    /// Hazards spiral out from the first one, one every SPIRAL_EVERY turns
+    Spiral,
+    /// Fixed concentric rings
+    Rings,
+    /// Hazards fill random cells over time, so only the ones already placed are known
+    Scatter,
+    /// Fixed islands joined by bridges
+    IslandsBridges,
+}
+
+impl GameMap {
+    /// Get the map from its name in the game info
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "royale" => GameMap::Royale,
+            "arcade_maze" => GameMap::ArcadeMaze,
+            "hz_spiral" => GameMap::Spiral,
+            "hz_rings" => GameMap::Rings,
+            "hz_scatter" => GameMap::Scatter,
+            "hz_islands_bridges" => GameMap::IslandsBridges,
+            _ => GameMap::Standard,
+        }
+    }
+
+    /// The name of the map in the game info
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMap::Standard => "standard",
+            GameMap::Royale => "royale",
+            GameMap::ArcadeMaze => "arcade_maze",
+            GameMap::Spiral => "hz_spiral",
+            GameMap::Rings => "hz_rings",
+            GameMap::Scatter => "hz_scatter",
+            GameMap::IslandsBridges => "hz_islands_bridges",
+        }
+    }
+
+    /// Whether the hazards on this map should never be entered at all
+    pub fn hazards_are_walls(&self) -> bool {
+        *self == GameMap::ArcadeMaze
+    }
+
+    /// The hazard this map adds at the start of `turn`, given the hazards placed before it.
+    /// Only predictable maps ever return something.
+    pub fn hazard_spawn(
+        &self,
+        hazards: &[Coordinate],
+        turn: i64,
+        width: u8,
+        height: u8,
+    ) -> Option<Coordinate> {
+        match self {
+            GameMap::Spiral => spiral_spawn(hazards, turn, width, height),
+            _ => None,
+        }
+    }
+
+    /// Predict the full hazard layout at `turn`, given the layout at `current_turn`
+    pub fn predict_hazards(
+        &self,
+        hazards: &[Coordinate],
+        current_turn: i64,
+        turn: i64,
+        width: u8,
+        height: u8,
+    ) -> Vec<Coordinate> {
+        let mut out = hazards.to_vec();
+        for next in current_turn + 1..=turn {
+            if let Some(hazard) = self.hazard_spawn(&out, next, width, height) {
+                out.push(hazard);
+            }
+        }
+        out
+    }
+}
+
+/// Next hazard on the spiral map.
+/// The first hazard is the center, and the next two give the direction and the way it turns.
+fn spiral_spawn(hazards: &[Coordinate], turn: i64, width: u8, height: u8) -> Option<Coordinate> {
+    if hazards.len() < 3 || turn <= 1 || (turn - 1) % SPIRAL_EVERY != 0 {
+        return None;
+    }
+    let first = hazards[1]
+        + Coordinate {
+            x: -hazards[0].x,
+            y: -hazards[0].y,
+        };
+    let second = hazards[2]
+        + Coordinate {
+            x: -hazards[1].x,
+            y: -hazards[1].y,
+        };
+    let clockwise = second
+        == Coordinate {
+            x: first.y,
+            y: -first.x,
+        };
+
+    // walk the spiral out to the index for this turn, runs go 1, 1, 2, 2, 3, 3, ...
+    let index = (turn - 1) / SPIRAL_EVERY;
+    let mut pos = hazards[0];
+    let mut dir = first;
+    let mut run = 1;
+    let mut walked = 0;
+    let mut turns = 0;
+    for _ in 0..index {
+        pos += dir;
+        walked += 1;
+        if walked == run {
+            walked = 0;
+            turns += 1;
+            if turns % 2 == 0 {
+                run += 1;
+            }
+            dir = if clockwise {
+                Coordinate {
+                    x: dir.y,
+                    y: -dir.x,
+                }
+            } else {
+                Coordinate {
+                    x: -dir.y,
+                    y: dir.x,
+                }
+            };
+        }
+    }
+    if pos.x < 0 || pos.y < 0 || pos.x >= width as i32 || pos.y >= height as i32 {
+        return None;
+    }
+    if hazards.contains(&pos) {
+        return None;
+    }
+    Some(pos)
+}
@@ -0,0 +1,152 @@
+use crate::{
+    board::Coordinate,
+    maps::GameMap,
+    rules::GameMode,
+    small::{SmallBattlesnake, SmallRequest, SNAKE_MAX},
+};
+
+/// Short text form of a position, sections split by `;`.
+/// The first section is the size, `11x11`, the rest start with a tag:
+/// - `t 12` the turn
+/// - `r solo` the ruleset name
+/// - `m hz_spiral` the map name
+/// - `f 2,4 8,6` food
+/// - `h 5,5 5,5` hazards, repeated to stack them
+/// - `y 100 2,7 2,8 3,8` you, health and then body from head to tail
+/// - `s 100 8,4 8,3 8,2` another snake
+///
+/// So a full position looks like `11x11;f 5,5;y 100 1,1 1,2 1,3;s 90 9,9 9,8 9,7`.
+pub trait Notation: Sized {
+    /// Parse a position, the error says what was wrong with it
+    fn from_notation(input: &str) -> Result<Self, String>;
+    /// Write the position out, dead snakes are left out
+    fn to_notation(&self) -> String;
+}
+
+/// Parse an x,y coordinate, it has to be on the board
+fn parse_coordinate(input: &str, width: u8, height: u8) -> Result<Coordinate, String> {
+    let (x, y) = input
+        .split_once(',')
+        .ok_or_else(|| format!("expected x,y but got {}", input))?;
+    let out = Coordinate {
+        x: x.parse().map_err(|_| format!("bad x in {}", input))?,
+        y: y.parse().map_err(|_| format!("bad y in {}", input))?,
+    };
+    if out.x < 0 || out.y < 0 || out.x >= width as i32 || out.y >= height as i32 {
+        return Err(format!("{} is off the {}x{} board", input, width, height));
+    }
+    Ok(out)
+}
+
+fn write_coordinates(coordinates: &[Coordinate]) -> String {
+    coordinates
+        .iter()
+        .map(|x| format!(" {},{}", x.x, x.y))
+        .collect()
+}
+
+impl Notation for SmallRequest {
+    fn from_notation(input: &str) -> Result<Self, String> {
+        let mut out = SmallRequest::new();
+        let mut sections = input.trim().split(';');
+        let size = sections.next().unwrap_or_default().trim();
+        let (width, height) = size
+            .split_once('x')
+            .ok_or_else(|| format!("expected a size like 11x11 but got {}", size))?;
+        out.board.width = width.parse().map_err(|_| "bad width".to_string())?;
+        out.board.height = height.parse().map_err(|_| "bad height".to_string())?;
+        if out.board.width > 11 || out.board.height > 11 {
+            return Err("boards bigger than 11x11 don't fit in the bitboards".to_string());
+        }
+
+        for section in sections {
+            let mut parts = section.split_whitespace();
+            let tag = match parts.next() {
+                Some(tag) => tag,
+                None => continue,
+            };
+            match tag {
+                "t" => {
+                    out.turn = parts
+                        .next()
+                        .and_then(|x| x.parse().ok())
+                        .ok_or("bad turn")?;
+                }
+                "r" => out.rules.mode = GameMode::from_name(parts.next().unwrap_or_default()),
+                "m" => out.rules.map = GameMap::from_name(parts.next().unwrap_or_default()),
+                "f" => {
+                    for part in parts {
+                        let food = parse_coordinate(part, out.board.width, out.board.height)?;
+                        out.board.food.push(food);
+                        out.board.food_bb |= u128::from(food);
+                    }
+                }
+                "h" => {
+                    for part in parts {
+                        let hazard = parse_coordinate(part, out.board.width, out.board.height)?;
+                        out.board.hazards.push(hazard);
+                        out.board.hazard_bb |= u128::from(hazard);
+                    }
+                }
+                "y" | "s" => {
+                    let health = parts
+                        .next()
+                        .and_then(|x| x.parse().ok())
+                        .ok_or("bad snake health")?;
+                    if out.board.snakes.len() == SNAKE_MAX {
+                        return Err(format!("more than {} snakes", SNAKE_MAX));
+                    }
+                    let body = parts
+                        .map(|x| parse_coordinate(x, out.board.width, out.board.height))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if body.is_empty() {
+                        return Err("snake without a body".to_string());
+                    }
+                    let id = out.board.snakes.len() as u8;
+                    if tag == "y" {
+                        out.you = id as usize;
+                    }
+                    out.board
+                        .snakes
+                        .push(SmallBattlesnake::new(id, health, body));
+                }
+                _ => return Err(format!("unknown section {}", tag)),
+            }
+        }
+        if out.board.snakes.is_empty() {
+            return Err("no snakes".to_string());
+        }
+        Ok(out)
+    }
+
+    fn to_notation(&self) -> String {
+        let mut out = format!("{}x{};t {}", self.board.width, self.board.height, self.turn);
+        if self.rules.mode != GameMode::Standard {
+            out += &format!(";r {}", self.rules.mode.name());
+        }
+        if self.rules.map != GameMap::Standard {
+            out += &format!(";m {}", self.rules.map.name());
+        }
+        if !self.board.food.is_empty() {
+            out += ";f";
+            out += &write_coordinates(&self.board.food);
+        }
+        if !self.board.hazards.is_empty() {
+            out += ";h";
+            out += &write_coordinates(&self.board.hazards);
+        }
+        for snake in &self.board.snakes {
+            if !snake.alive {
+                continue;
+            }
+            let tag = if snake.id as usize == self.you {
+                "y"
+            } else {
+                "s"
+            };
+            out += &format!(";{} {}", tag, snake.health);
+            out += &write_coordinates(&snake.body);
+        }
+        out
+    }
+}
@@ -0,0 +1,67 @@
+use crate::maps::GameMap;
+
+/// Game modes that change how the game is simulated or searched
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameMode {
+    /// Normal multiplayer game
+    Standard,
+    /// Single snake, the goal is to survive as long as possible
+    Solo,
+    /// Hazards close in over time
+    Royale,
+    /// Snakes never lose their tail
+    Constrictor,
+    /// Board edges wrap around
+    Wrapped,
+}
+
+/// The parts of the ruleset that the simulation cares about
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rules {
+    /// game mode, taken from the ruleset name
+    pub mode: GameMode,
+    /// percent chance of a food spawning each turn
+    pub food_spawn_chance: u8,
+    /// minimum amount of food that is always on the board
+    pub minimum_food: u8,
+    /// damage taken per turn for each hazard stacked on the head
+    pub hazard_damage: u8,
+    /// the map, which decides where hazards go
+    pub map: GameMap,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            mode: GameMode::Standard,
+            food_spawn_chance: 15,
+            minimum_food: 1,
+            hazard_damage: 14,
+            map: GameMap::Standard,
+        }
+    }
+}
+
+impl GameMode {
+    /// Get the mode from the ruleset name, unknown rulesets are treated as standard
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "solo" => GameMode::Solo,
+            "royale" => GameMode::Royale,
+            "constrictor" => GameMode::Constrictor,
+            "wrapped" => GameMode::Wrapped,
+            _ => GameMode::Standard,
+        }
+    }
+
+    /// The ruleset name for this mode
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Standard => "standard",
+            GameMode::Solo => "solo",
+            GameMode::Royale => "royale",
+            GameMode::Constrictor => "constrictor",
+            GameMode::Wrapped => "wrapped",
+        }
+    }
+}
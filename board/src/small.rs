@@ -1,4 +1,4 @@
-use crate::board::Coordinate;
+use crate::{board::Coordinate, rules::Rules};
 /// Maximum number of snakes that I can handle
 pub const SNAKE_MAX: usize = 4;
 
@@ -6,12 +6,14 @@ pub const SNAKE_MAX: usize = 4;
 /// A key point here is that the you member is the index of you in the snakes list itself.
 #[derive(Clone, Debug)]
 pub struct SmallRequest {
-    /// current turn (not updated by make / unmake move)
+    /// current turn, make / unmake move step it forwards and back
     pub turn: i64,
     /// the board
     pub board: SmallBoard,
     /// the you index in the board.snakes array
     pub you: usize,
+    /// the ruleset of the game
+    pub rules: Rules,
 }
 
 /// The board info with stripped out uselsess info
@@ -19,6 +21,8 @@ pub struct SmallRequest {
 pub struct SmallBoard {
     /// Bit board of food
     pub food_bb: u128,
+    /// Bit board of hazards, stacked hazards only show up once
+    pub hazard_bb: u128,
     /// zobrist hash
     pub zobrist: u64,
     /// height of board in cells
@@ -55,6 +59,27 @@ pub struct SmallBattlesnake {
     pub body_bb: u128,
 }
 
+impl SmallBattlesnake {
+    /// Make a live snake from its body, head first
+    pub fn new(id: u8, health: u8, body: Vec<Coordinate>) -> SmallBattlesnake {
+        let mut body_bb = 0;
+        // exclude the head because reasons
+        for coord in &body[1..] {
+            body_bb |= u128::from(*coord);
+        }
+        SmallBattlesnake {
+            id,
+            health,
+            head: body[0],
+            head_bb: u128::from(body[0]),
+            length: body.len() as u16,
+            body,
+            alive: true,
+            body_bb,
+        }
+    }
+}
+
 impl Default for SmallRequest {
     fn default() -> Self {
         Self::new()
@@ -74,8 +99,10 @@ impl SmallRequest {
                 hazards: vec![],
                 snakes: vec![],
                 food_bb: 0,
+                hazard_bb: 0,
             },
             you: 0,
+            rules: Rules::default(),
         }
     }
     /// Get the amount of alive snakes
@@ -5,17 +5,31 @@ extern crate rocket;
 #[macro_use]
 extern crate rocket_contrib;
 
-use std::time::Instant;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
+use board::ascii::render;
 use board::board::GameRequest;
 
+use evaluation::evaluator::StandardEval;
+use evaluation::trap::Traps;
+use evaluation::weights::EvalWeights;
+
 use movegen::genmove::*;
+use movegen::makeunmake::Direction;
+use movegen::tracker::Tracker;
 
 use rocket::config::{Config, Environment};
 use rocket::http::Status;
 use rocket::routes;
+use rocket::State;
 use rocket_contrib::json::{Json, JsonValue};
 use search::search::Search;
+/// Deepest the search goes, however much time there is
+const MAX_DEPTH: u8 = 20;
+/// Time kept back from the game's timeout for the request to get here and the answer back
+const NETWORK_MARGIN: Duration = Duration::from_millis(150);
+
 #[get("/")]
 fn handle_index() -> JsonValue {
     return json!({
@@ -33,17 +47,35 @@ fn handle_start(_start_req: Json<GameRequest>) -> Status {
 }
 
 #[post("/move", format = "json", data = "<move_req>")]
-fn handle_move(move_req: Json<GameRequest>) -> JsonValue {
+fn handle_move(
+    move_req: Json<GameRequest>,
+    tracker: State<Mutex<Tracker>>,
+    evaluator: State<StandardEval>,
+) -> JsonValue {
+    let changes = tracker.lock().unwrap().observe(&move_req);
+    // only worth the noise when debugging a game locally
+    if cfg!(debug_assertions) {
+        if let Some(changes) = changes {
+            println!("{:?}", changes);
+        }
+    }
     let mut small = move_req.into_small();
-    let t0 = Instant::now();
-    let eval = small.minimax(7, i32::MIN, i32::MAX, true, None);
-    let t1 = Instant::now();
     let you_moves = small.snake_moves(small.you);
     if you_moves.len() == 1 {
         return json!({ "move":  you_moves[0].direction.to_string()});
     }
+    // moves into dead ends are vetoed, unless every move is one
+    let allowed = small.veto_traps(small.you);
+    if allowed.len() == 1 {
+        return json!({ "move":  allowed[0].direction.to_string()});
+    }
+    let t0 = Instant::now();
+    // a depth still running when the time is up is abandoned for the last one that finished
+    let budget = Duration::from_millis(move_req.timeout()).saturating_sub(NETWORK_MARGIN);
+    let (eval, depth) = small.iterative_deepening(evaluator.inner(), MAX_DEPTH, budget);
+    let t1 = Instant::now();
     println!("{:?}", small.snake_moves(small.you));
-    println!("{:?}", t1 - t0);
+    println!("depth {} in {:?}", depth, t1 - t0);
     // assert!(t0
     // .board
     // .food
@@ -58,11 +90,41 @@ fn handle_move(move_req: Json<GameRequest>) -> JsonValue {
         move_req.turn, eval.score, eval.direction
     );
 
-    return json!({ "move":  eval.direction.unwrap().to_string()});
+    let searched = match eval.direction {
+        Some(direction) if allowed.iter().any(|x| x.direction == direction) => Some(direction),
+        // the search liked a dead end, so take the move with the most room instead
+        Some(direction) => Some(
+            allowed
+                .iter()
+                .max_by_key(|x| small.room_after(**x))
+                .map_or(direction, |x| x.direction),
+        ),
+        None => None,
+    };
+    // nothing to search for, so take the least deadly move there is
+    let direction = searched.unwrap_or_else(|| {
+        small
+            .tagged_moves(small.you, MoveMode::Legal)
+            .iter()
+            .min_by_key(|x| x.reason.safety())
+            .map_or(Direction::Up, |x| x.mov.direction)
+    });
+    return json!({ "move":  direction.to_string()});
+}
+
+/// Debug view of how the eval sees a request, also printed next to the board
+#[post("/explain", format = "json", data = "<explain_req>")]
+fn handle_explain(explain_req: Json<GameRequest>, evaluator: State<StandardEval>) -> JsonValue {
+    let small = explain_req.into_small();
+    let explanation = evaluator.explain(&small);
+    let board = render(&small);
+    println!("{}", explanation.beside(&board));
+    json!({ "board": board, "explanation": explanation })
 }
 
-#[post("/end", format = "json", data = "<_end_req>")]
-fn handle_end(_end_req: Json<GameRequest>) -> Status {
+#[post("/end", format = "json", data = "<end_req>")]
+fn handle_end(end_req: Json<GameRequest>, tracker: State<Mutex<Tracker>>) -> Status {
+    tracker.lock().unwrap().forget(end_req.game_id());
     println!("end");
     Status::Ok
 }
@@ -71,6 +133,8 @@ fn main() {
     let address = "0.0.0.0";
     let env_port = "8000";
     let port = env_port.parse::<u16>().unwrap();
+    let weights = EvalWeights::load().expect("couldn't load the eval weights");
+    println!("{:?}", weights);
 
     let config = Config::build(Environment::Development)
         .address(address)
@@ -78,9 +142,17 @@ fn main() {
         .finalize()
         .unwrap();
     rocket::custom(config)
+        .manage(Mutex::new(Tracker::new()))
+        .manage(StandardEval::new(weights))
         .mount(
             "/",
-            routes![handle_index, handle_start, handle_move, handle_end],
+            routes![
+                handle_index,
+                handle_start,
+                handle_move,
+                handle_explain,
+                handle_end
+            ],
         )
         .launch();
 }
@@ -0,0 +1,31 @@
+use std::{fs, path::Path, process};
+
+use board::{ascii::render, board::GameRequest, notation::Notation, small::SmallRequest};
+use evaluation::{evaluator::StandardEval, weights::EvalWeights};
+
+/// Prints the eval of a position term by term, next to the board, the survival eval in solo games.
+/// `explain <position>` where the position is a game request json file or notation,
+/// the weights are loaded the same way the engine loads them.
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 2 {
+        eprintln!("usage: explain <json file | notation>");
+        process::exit(2);
+    }
+    let state = if Path::new(&args[1]).is_file() {
+        let contents = fs::read_to_string(&args[1]).expect("Something went wrong reading the file");
+        let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
+        seralized.into_small()
+    } else {
+        SmallRequest::from_notation(&args[1]).unwrap_or_else(|err| {
+            eprintln!("bad position: {}", err);
+            process::exit(2);
+        })
+    };
+    let weights = EvalWeights::load().unwrap_or_else(|err| {
+        eprintln!("bad weights: {}", err);
+        process::exit(2);
+    });
+    let explanation = StandardEval::new(weights).explain(&state);
+    print!("{}", explanation.beside(&render(&state)));
+}
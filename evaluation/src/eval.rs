@@ -1,33 +1,368 @@
-use board::{board::Coordinate, small::SmallRequest};
+use board::{
+    bitboard::board_mask,
+    small::{SmallRequest, SNAKE_MAX},
+};
+use movegen::threat::Threats;
+
+use crate::{
+    explain::{Explanation, Term},
+    flood::{health_cost, reachable, voronoi},
+    trap::trapped_with,
+    weights::EvalWeights,
+};
 
 pub trait StaticEval {
-    fn static_eval(&self) -> i32;
+    /// Score from your point of view
+    fn static_eval(&self, weights: &EvalWeights) -> i32;
+    /// Score of every snake from its own point of view, by id
+    fn eval_all(&self, weights: &EvalWeights) -> [i32; SNAKE_MAX];
+    /// Raw terms of every snake, by id, dead snakes get all zeroes
+    fn features(&self) -> [Features; SNAKE_MAX];
+    /// Your score broken down term by term
+    fn static_eval_explain(&self, weights: &EvalWeights) -> Explanation;
+    /// Score for solo games, an estimate of how many more turns you can survive
+    fn survival_eval(&self, weights: &EvalWeights) -> i32;
+    /// The survival eval as an explanation
+    fn survival_explain(&self, weights: &EvalWeights) -> Explanation;
+    /// Cells each snake gets to before everyone else, by id, dead snakes get none
+    fn territory(&self) -> Vec<u128>;
 }
 
-fn manhattan_dist(p1: &Coordinate, p2: &Coordinate) -> i32 {
-    (p1.x - p2.x).abs() + (p1.y - p2.y).abs()
+/// Cells that are blocked after n moves, by n, the last one holding from then on.
+/// Bodies block until their tail has moved off, and so do walls and hazards on maps where they are walls.
+pub fn blocked_after(state: &SmallRequest) -> Vec<u128> {
+    let mut occupied = 0;
+    // freed[n] has the cells that tails have moved off n moves from now
+    let mut freed = vec![0u128; 2];
+    for snake in &state.board.snakes {
+        if !snake.alive {
+            continue;
+        }
+        let length = snake.body.len();
+        if freed.len() <= length {
+            freed.resize(length + 1, 0);
+        }
+        for (i, cell) in snake.body.iter().enumerate() {
+            occupied |= u128::from(*cell);
+            // a stacked tail leaves with the segment in front of it
+            if i > 0 && snake.body[i - 1] == *cell {
+                continue;
+            }
+            freed[length - i] |= u128::from(*cell);
+        }
+    }
+    let mut walls = !board_mask(state.board.width, state.board.height);
+    if state.rules.map.hazards_are_walls() {
+        walls |= state.board.hazard_bb;
+    }
+    let mut blocked = vec![occupied | walls];
+    for cells in freed.iter().skip(1) {
+        let last = blocked[blocked.len() - 1];
+        blocked.push(last & !cells);
+    }
+    blocked
 }
 
+/// Hazards that hurt rather than block, as layers where `stacks[k]` has the cells with more than k hazards on them.
+/// Empty when there are none or the map treats them as walls.
+pub fn hazard_stacks(state: &SmallRequest) -> Vec<u128> {
+    let mut out: Vec<u128> = vec![];
+    if state.rules.map.hazards_are_walls() || state.rules.hazard_damage == 0 {
+        return out;
+    }
+    for hazard in &state.board.hazards {
+        let bit = u128::from(*hazard);
+        match out.iter().position(|x| x & bit == 0) {
+            Some(layer) => out[layer] |= bit,
+            None => out.push(bit),
+        }
+    }
+    out
+}
+
+/// Health right after eating, food that costs more than that can't be reached alive
+const MAX_HEALTH: i32 = 100;
+
+/// Health to spare once at the food below which getting there starts to be urgent
+const URGENT_SLACK: i32 = 20;
+
+/// Health at or below which walking through hazards is too dangerous to count on
+fn low_health(state: &SmallRequest) -> i32 {
+    2 * state.rules.hazard_damage as i32
+}
+
+/// Raw values of every eval term for one snake, before the weights are applied
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Features {
+    /// Body segments
+    pub length: i32,
+    /// Segments more than the longest snake, so never above 0
+    pub length_difference: i32,
+    /// 1 when at least as long as every other snake
+    pub biggest: i32,
+    /// Cells reached before everyone else
+    pub territory: i32,
+    /// Segments the territory is short of the length
+    pub cramped: i32,
+    /// Health left
+    pub health: i32,
+    /// Health it costs to get to the nearest food in the territory, or twice that to food tied with another head, doubled again when not clearly the longest.
+    /// Without any food it is more than the most it could cost
+    pub food_distance: i32,
+    /// How little health is left to spare once at that food, squared so it gets worse faster the closer it gets to none,
+    /// 0 with URGENT_SLACK or more to spare
+    pub food_urgency: i32,
+    /// Cells shorter snakes can meet the head in, more the fewer ways out they have
+    pub aggression: i32,
+    /// Cells equal or longer snakes can meet the head in, more the fewer ways out there are
+    pub exposure: i32,
+    /// 1 when every move leads into a dead end
+    pub trapped: i32,
+    /// Other snakes whose every move leads into a dead end
+    pub sealed: i32,
+    /// Hazards in the territory, stacked ones counting once each
+    pub hazard: i32,
+}
+
+impl Features {
+    /// Every term with its weight, in the order the weights list them
+    pub fn terms(&self, weights: &EvalWeights) -> [Term; 13] {
+        [
+            Term::new("length", self.length, weights.length, false),
+            Term::new(
+                "length_difference",
+                self.length_difference,
+                weights.length_difference,
+                false,
+            ),
+            Term::new("biggest", self.biggest, weights.biggest, false),
+            Term::new("territory", self.territory, weights.territory, false),
+            Term::new("cramped", self.cramped, weights.cramped, true),
+            Term::new(
+                "food_distance",
+                self.food_distance,
+                weights.food_distance,
+                true,
+            ),
+            Term::new(
+                "food_urgency",
+                self.food_urgency,
+                weights.food_urgency,
+                true,
+            ),
+            Term::new("health", self.health, weights.health, false),
+            Term::new("aggression", self.aggression, weights.aggression, false),
+            Term::new("exposure", self.exposure, weights.exposure, true),
+            Term::new("trapped", self.trapped, weights.trapped, true),
+            Term::new("sealed", self.sealed, weights.sealed, false),
+            Term::new("hazard", self.hazard, weights.hazard, true),
+        ]
+    }
+
+    /// Weigh the features into a single score
+    pub fn score(&self, weights: &EvalWeights) -> i32 {
+        self.terms(weights).iter().map(|x| x.contribution).sum()
+    }
+}
+
+/// What eval_all gives dead snakes and empty slots, the same score the search gives a loss
+pub const LOST: i32 = i32::MIN + 1000;
+
 impl StaticEval for SmallRequest {
-    fn static_eval(&self) -> i32 {
-        let mut dist_food = i32::MAX;
-        let you_head = self.board.snakes[self.you].head;
-        let you_length = self.board.snakes[self.you].length;
-        for food in &self.board.food {
-            if manhattan_dist(&self.board.snakes[self.you].body[0], food) < dist_food {
-                dist_food = manhattan_dist(&you_head, food);
+    fn static_eval(&self, weights: &EvalWeights) -> i32 {
+        if !self.board.snakes[self.you].alive {
+            return LOST;
+        }
+        self.features()[self.you].score(weights)
+    }
+
+    fn static_eval_explain(&self, weights: &EvalWeights) -> Explanation {
+        if !self.board.snakes[self.you].alive {
+            return Explanation::lost();
+        }
+        Explanation::new(&self.features()[self.you], weights)
+    }
+
+    fn eval_all(&self, weights: &EvalWeights) -> [i32; SNAKE_MAX] {
+        let features = self.features();
+        let mut out = [LOST; SNAKE_MAX];
+        for snake in &self.board.snakes {
+            if snake.alive {
+                out[snake.id as usize] = features[snake.id as usize].score(weights);
             }
         }
-        let mut biggest_length = 0;
+        out
+    }
+
+    fn features(&self) -> [Features; SNAKE_MAX] {
+        let mut out = [Features::default(); SNAKE_MAX];
+        // everything the snakes share is worked out once
+        let blocked = blocked_after(self);
+        let territory = territory_with(self, &blocked);
+        let trapped = trapped_with(self, &blocked);
+        let threats = self.threat_map();
+        let mut occupied = 0;
         for snake in &self.board.snakes {
-            if snake.length >= biggest_length {
-                biggest_length = snake.length;
+            if snake.alive {
+                occupied |= snake.body_bb;
             }
         }
-        let biggest = biggest_length <= you_length;
-        let difference = you_length as i32 - biggest_length as i32;
-        (self.board.snakes[self.you].body.len()) as i32 * 10
-            + difference as i32 * 20
-            + biggest as i32 * 1000
+        let passable = board_mask(self.board.width, self.board.height) & !occupied;
+        let stacks = hazard_stacks(self);
+        let hazards = stacks.first().copied().unwrap_or(0);
+        let damage = self.rules.hazard_damage as u32;
+        let biggest_length = self
+            .board
+            .snakes
+            .iter()
+            .map(|x| x.length)
+            .max()
+            .unwrap_or(0);
+        // food nobody gets to first, which is food two equally long heads get to at once
+        let tied_food = self.board.food_bb & !territory.iter().fold(0, |acc, x| acc | x);
+
+        for snake in &self.board.snakes {
+            if !snake.alive {
+                continue;
+            }
+            let id = snake.id as usize;
+            let length = snake.length as i32;
+            let features = &mut out[id];
+            features.length = snake.body.len() as i32;
+            features.length_difference = length - biggest_length as i32;
+            features.biggest = (biggest_length <= snake.length) as i32;
+
+            let health = snake.health as i32;
+            let mut owned = territory[id];
+            // low on health, the cells behind hazards might as well not be there
+            if hazards != 0 && health <= low_health(self) {
+                owned &= reachable(snake.head_bb, passable & !hazards);
+            }
+            // room to move in, and being boxed into less room than your own length is a slow death
+            features.territory = (owned & !hazards).count_ones() as i32;
+            features.cramped = (length - features.territory).max(0);
+            features.hazard = stacks.iter().map(|x| (owned & x).count_ones() as i32).sum();
+
+            // food in your territory is food nobody else gets to first, hazards on the way cost health
+            let cost = |food| {
+                health_cost(
+                    snake.head_bb,
+                    food,
+                    passable,
+                    &stacks,
+                    damage,
+                    MAX_HEALTH as u32,
+                )
+            };
+            // no food at all is worse than any food there is
+            let no_food = MAX_HEALTH + 1;
+            // tied food ends in a head to head if both go for it, so it counts as twice as far
+            let dist = cost(self.board.food_bb & owned)
+                .map(|x| x as i32)
+                .or_else(|| cost(tied_food).map(|x| (2 * x as i32).min(no_food)))
+                .unwrap_or(no_food);
+            let longest_other = self
+                .board
+                .snakes
+                .iter()
+                .filter(|x| x.alive && x.id != snake.id)
+                .map(|x| x.length as i32)
+                .max()
+                .unwrap_or(0);
+            // not being clearly the longest makes it worth going further for food
+            let need = if length < longest_other + 2 { 2 } else { 1 };
+            features.health = health;
+            features.food_distance = dist * need;
+            // plenty of health to spare doesn't matter, but every turn less of it matters more than the one before
+            let spare = (health - dist).clamp(0, URGENT_SLACK);
+            features.food_urgency = (URGENT_SLACK - spare).pow(2);
+
+            let reach = threats.reach[id];
+            // cells you can go to without risking a head to head
+            let escapes = (reach & !threats.danger(id)).count_ones() as i32;
+            for other in &self.board.snakes {
+                if !other.alive || other.id == snake.id {
+                    continue;
+                }
+                let other_reach = threats.reach[other.id as usize];
+                let shared = (reach & other_reach).count_ones() as i32;
+                if other.length < snake.length {
+                    // a snake with fewer ways out is more likely to have to come your way
+                    let other_escapes = (other_reach & !reach).count_ones() as i32;
+                    features.aggression += shared * (4 - other_escapes);
+                } else {
+                    features.exposure += shared * (4 - escapes);
+                }
+                if trapped[other.id as usize] {
+                    features.sealed += 1;
+                }
+            }
+            features.trapped = trapped[id] as i32;
+        }
+        out
+    }
+
+    fn survival_eval(&self, weights: &EvalWeights) -> i32 {
+        survival_turns(self) * weights.survival
+    }
+
+    fn survival_explain(&self, weights: &EvalWeights) -> Explanation {
+        Explanation::survival(survival_turns(self), weights)
+    }
+
+    fn territory(&self) -> Vec<u128> {
+        territory_with(self, &blocked_after(self))
+    }
+}
+
+/// How many more turns you can expect to survive, what the survival eval weighs
+fn survival_turns(state: &SmallRequest) -> i32 {
+    let you = &state.board.snakes[state.you];
+    let mut occupied = 0;
+    for snake in &state.board.snakes {
+        if snake.alive {
+            occupied |= snake.body_bb;
+        }
+    }
+    let passable = board_mask(state.board.width, state.board.height) & !occupied;
+    let stacks = hazard_stacks(state);
+    let hazards = stacks.first().copied().unwrap_or(0);
+    let health = you.health as i32;
+    let mut turns = health;
+    // eating resets health, so food reachable on the health left pushes the horizon out, cheaper food being the safer bet
+    if let Some(cost) = health_cost(
+        you.head_bb,
+        state.board.food_bb,
+        passable,
+        &stacks,
+        state.rules.hazard_damage as u32,
+        health as u32,
+    ) {
+        turns += 100 - cost as i32;
+    }
+    // low on health, the room behind hazards can't be counted on
+    let room = if health <= low_health(state) {
+        passable & !hazards
+    } else {
+        passable
+    };
+    // boxed in, can't outlast the room that is left
+    let space = reachable(you.head_bb, room).count_ones() as i32;
+    if space < you.length as i32 {
+        turns = turns.min(space);
+    }
+    turns
+}
+
+/// Territory of every snake, given what blocked_after gives
+fn territory_with(state: &SmallRequest, blocked: &[u128]) -> Vec<u128> {
+    let mut heads = vec![];
+    let mut lengths = vec![];
+    for snake in &state.board.snakes {
+        heads.push(if snake.alive { snake.head_bb } else { 0 });
+        lengths.push(if snake.alive { snake.length } else { 0 });
     }
+    voronoi(&heads, &lengths, |step| {
+        !blocked[(step as usize).min(blocked.len() - 1)]
+    })
 }
@@ -0,0 +1,96 @@
+use board::{rules::GameMode, small::SmallRequest};
+
+use crate::{eval::StaticEval, explain::Explanation, weights::EvalWeights};
+
+/// Something that scores positions from your point of view, for the search to use at its leaves
+pub trait Evaluator: Send + Sync {
+    /// Score of the position, higher is better for you
+    fn eval(&self, state: &SmallRequest) -> i32;
+    /// Whether the search may cut down the moves of snakes too far away from everyone to matter, see joint_moves_near.
+    /// The result only stays the same when the score doesn't depend on where those snakes are, so it is off unless an evaluator says otherwise.
+    fn prune_distant(&self) -> bool {
+        false
+    }
+}
+
+/// Any function from a position to a score is an evaluator, handy for trying out ideas
+impl<F: Fn(&SmallRequest) -> i32 + Send + Sync> Evaluator for F {
+    fn eval(&self, state: &SmallRequest) -> i32 {
+        self(state)
+    }
+}
+
+/// The built in eval with a set of weights, the survival eval in solo games and the static eval otherwise
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StandardEval {
+    /// Weights of the eval terms
+    pub weights: EvalWeights,
+}
+
+impl StandardEval {
+    pub fn new(weights: EvalWeights) -> Self {
+        Self { weights }
+    }
+
+    /// What eval gives, broken down term by term
+    pub fn explain(&self, state: &SmallRequest) -> Explanation {
+        if state.rules.mode == GameMode::Solo {
+            state.survival_explain(&self.weights)
+        } else {
+            state.static_eval_explain(&self.weights)
+        }
+    }
+}
+
+impl Evaluator for StandardEval {
+    fn eval(&self, state: &SmallRequest) -> i32 {
+        if state.rules.mode == GameMode::Solo {
+            state.survival_eval(&self.weights)
+        } else {
+            state.static_eval(&self.weights)
+        }
+    }
+
+    /// Territory and food races see every head, so this does change the score, and sometimes the move.
+    /// It is on anyway, with four snakes it makes the search about three times faster, which is worth more than the exact answer.
+    fn prune_distant(&self) -> bool {
+        true
+    }
+}
+
+/// Several evaluators added up, each times its own weight.
+/// Two evals side by side, one weighted 1 and the other 0, make it easy to switch between them without a rebuild.
+#[derive(Default)]
+pub struct WeightedSum {
+    /// (weight, evaluator)
+    parts: Vec<(i32, Box<dyn Evaluator>)>,
+}
+
+impl WeightedSum {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an evaluator with its weight
+    pub fn with<E: Evaluator + 'static>(mut self, weight: i32, evaluator: E) -> Self {
+        self.parts.push((weight, Box::new(evaluator)));
+        self
+    }
+}
+
+impl Evaluator for WeightedSum {
+    fn eval(&self, state: &SmallRequest) -> i32 {
+        let total: i64 = self
+            .parts
+            .iter()
+            .map(|(weight, evaluator)| *weight as i64 * evaluator.eval(state) as i64)
+            .sum();
+        total.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+    }
+
+    fn prune_distant(&self) -> bool {
+        self.parts
+            .iter()
+            .all(|(_, evaluator)| evaluator.prune_distant())
+    }
+}
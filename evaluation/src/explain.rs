@@ -0,0 +1,107 @@
+use std::fmt::{self, Display};
+
+use serde::Serialize;
+
+use crate::{
+    eval::{Features, LOST},
+    weights::EvalWeights,
+};
+
+/// One term of the eval
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Term {
+    /// Name of the term, the same as its weight
+    pub name: &'static str,
+    /// Raw value before weighing
+    pub value: i32,
+    /// Weight it got
+    pub weight: i32,
+    /// What it added to the score, penalties being taken off
+    pub contribution: i32,
+}
+
+/// Why the eval gave the score it did
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Explanation {
+    /// Every term, in the order the weights list them
+    pub terms: Vec<Term>,
+    /// The score, the sum of the contributions
+    pub total: i32,
+}
+
+impl Term {
+    /// Weigh a raw value, a penalty being taken off the score
+    pub fn new(name: &'static str, value: i32, weight: i32, penalty: bool) -> Self {
+        Self {
+            name,
+            value,
+            weight,
+            contribution: if penalty {
+                -value * weight
+            } else {
+                value * weight
+            },
+        }
+    }
+}
+
+impl Explanation {
+    /// Break the features down term by term, the same terms Features::score adds up
+    pub fn new(features: &Features, weights: &EvalWeights) -> Self {
+        Self::from_terms(features.terms(weights).to_vec())
+    }
+
+    /// The survival eval, a single term
+    pub fn survival(turns: i32, weights: &EvalWeights) -> Self {
+        Self::from_terms(vec![Term::new("survival", turns, weights.survival, false)])
+    }
+
+    /// You are dead, so there is nothing to break down
+    pub fn lost() -> Self {
+        Self {
+            terms: vec![],
+            total: LOST,
+        }
+    }
+
+    fn from_terms(terms: Vec<Term>) -> Self {
+        let total = terms.iter().map(|x| x.contribution).sum();
+        Self { terms, total }
+    }
+
+    /// The breakdown as a table, one line per term
+    pub fn lines(&self) -> Vec<String> {
+        let mut out = vec![format!(
+            "{:<18}{:>7}{:>7}{:>8}",
+            "term", "value", "weight", "score"
+        )];
+        for term in &self.terms {
+            out.push(format!(
+                "{:<18}{:>7}{:>7}{:>8}",
+                term.name, term.value, term.weight, term.contribution
+            ));
+        }
+        out.push(format!("{:<32}{:>8}", "total", self.total));
+        out
+    }
+
+    /// The table to the right of a picture of the board
+    pub fn beside(&self, board: &[String]) -> String {
+        let table = self.lines();
+        let board_width = board.iter().map(|x| x.len()).max().unwrap_or(0);
+        let mut out = String::new();
+        for n in 0..board.len().max(table.len()) {
+            let left = board.get(n).map_or("", |x| x.as_str());
+            let right = table.get(n).map_or("", |x| x.as_str());
+            out += format!("{:<width$}   {}", left, right, width = board_width).trim_end();
+            out += "\n";
+        }
+        out
+    }
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.lines().join("\n"))
+    }
+}
@@ -0,0 +1,130 @@
+use board::bitboard::neighbours;
+
+/// All cells reachable from `from` by only walking through `passable` cells.
+/// The starting cells are always included.
+pub fn reachable(from: u128, passable: u128) -> u128 {
+    let mut seen = from;
+    loop {
+        let next = seen | (neighbours(seen) & passable);
+        if next == seen {
+            return seen;
+        }
+        seen = next;
+    }
+}
+
+/// All cells reachable from `from`, where `passable` gives the cells that are free after the given amount of moves.
+/// A snake can't stand still, so a cell that frees up later can only be waited for by wandering around the cells already reached,
+/// one move per cell. `last_free` is the move after which nothing frees up anymore.
+pub fn reachable_timed<F: Fn(u32) -> u128>(from: u128, passable: F, last_free: u32) -> u128 {
+    let mut seen = from;
+    let mut step = 1;
+    loop {
+        let next = seen | (neighbours(seen) & passable(step));
+        if next == seen && (step > last_free || step >= seen.count_ones()) {
+            return seen;
+        }
+        seen = next;
+        step += 1;
+    }
+}
+
+/// Amount of moves needed to get from `from` onto any of the `targets`,
+/// walking only through `passable` cells (targets count as passable).
+/// None if no target can be reached.
+pub fn distance(from: u128, targets: u128, passable: u128) -> Option<u32> {
+    let passable = passable | targets;
+    let mut seen = from;
+    let mut frontier = from;
+    let mut dist = 0;
+    while frontier != 0 {
+        if frontier & targets != 0 {
+            return Some(dist);
+        }
+        frontier = neighbours(frontier) & passable & !seen;
+        seen |= frontier;
+        dist += 1;
+    }
+    None
+}
+
+/// Split the board between snakes, each cell going to the one whose head gets there first.
+/// Heads move at the same time, a cell reached by several at once goes to the strictly longest and to nobody on a tie.
+/// `passable` gives the cells that are free after the given amount of moves, so tails can move out of the way.
+/// Heads of 0 are left out and get no cells.
+pub fn voronoi<F: Fn(u32) -> u128>(heads: &[u128], lengths: &[u16], passable: F) -> Vec<u128> {
+    let mut owned: Vec<u128> = heads.to_vec();
+    let mut claimed = heads.iter().fold(0, |acc, x| acc | x);
+    // every tail is gone after the longest snake's length in moves, nothing new frees up after that
+    let last_free = lengths.iter().copied().max().unwrap_or(0) as u32;
+    let mut step = 1;
+    loop {
+        let free = passable(step) & !claimed;
+        let grown: Vec<u128> = owned
+            .iter()
+            .map(|x| if *x == 0 { 0 } else { neighbours(*x) & free })
+            .collect();
+        let any = grown.iter().fold(0, |acc, x| acc | x);
+        if any == 0 && step > last_free {
+            return owned;
+        }
+        for (id, cells) in grown.iter().enumerate() {
+            let mut won = *cells;
+            for (other, other_cells) in grown.iter().enumerate() {
+                if other != id && lengths[other] >= lengths[id] {
+                    won &= !other_cells;
+                }
+            }
+            owned[id] |= won;
+        }
+        claimed |= any;
+        step += 1;
+    }
+}
+
+/// Least health it takes to get from `from` onto any of the `targets`, walking only through `passable` cells
+/// (targets count as passable). Every move costs 1, and a move into a cell with hazards on it costs `damage` more per hazard.
+/// `stacks[k]` has the cells with more than k hazards on them, food cancels out the hazard so targets always cost 1.
+/// None if no target can be reached for at most `limit`.
+pub fn health_cost(
+    from: u128,
+    targets: u128,
+    passable: u128,
+    stacks: &[u128],
+    damage: u32,
+    limit: u32,
+) -> Option<u32> {
+    let passable = passable | targets;
+    // cells by the health it takes to get to them, handed out cheapest first
+    let mut buckets = vec![0u128; limit as usize + 1];
+    buckets[0] = from;
+    let mut seen = 0;
+    for cost in 0..=limit {
+        let frontier = buckets[cost as usize] & !seen;
+        if frontier == 0 {
+            continue;
+        }
+        seen |= frontier;
+        if frontier & targets != 0 {
+            return Some(cost);
+        }
+        let next = neighbours(frontier) & passable & !seen;
+        let mut deeper = next & !targets;
+        let mut here = next & targets;
+        let mut hazards = 0;
+        loop {
+            here |= deeper & !stacks.get(hazards).copied().unwrap_or(0);
+            let step = cost + 1 + damage * hazards as u32;
+            if step <= limit {
+                buckets[step as usize] |= here;
+            }
+            deeper &= stacks.get(hazards).copied().unwrap_or(0);
+            if deeper == 0 {
+                break;
+            }
+            here = 0;
+            hazards += 1;
+        }
+    }
+    None
+}
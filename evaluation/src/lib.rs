@@ -1 +1,367 @@
 pub mod eval;
+/// Evaluators the search can be given, and ways to combine them
+pub mod evaluator;
+/// Term by term breakdown of the eval
+pub mod explain;
+/// Bitboard flood fills and path lengths
+pub mod flood;
+/// Dead end detection
+pub mod trap;
+/// Weights of the eval terms, loaded at start up
+pub mod weights;
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use board::board::{Coordinate, GameRequest};
+    use board::notation::Notation;
+    use board::small::SmallRequest;
+    use movegen::makeunmake::{Direction, Move};
+    use pretty_assertions::assert_eq;
+
+    use crate::eval::{StaticEval, LOST};
+    use crate::evaluator::{Evaluator, StandardEval, WeightedSum};
+    use crate::flood::health_cost;
+    use crate::trap::Traps;
+    use crate::weights::{EvalWeights, WEIGHTS_FILE_VAR, WEIGHT_VAR_PREFIX};
+
+    fn position(notation: &str) -> SmallRequest {
+        SmallRequest::from_notation(notation).expect("bad notation")
+    }
+
+    /// Scores everything 1, and says whether distant snakes may be pruned
+    struct Pruning(bool);
+
+    impl Evaluator for Pruning {
+        fn eval(&self, _state: &SmallRequest) -> i32 {
+            1
+        }
+
+        fn prune_distant(&self) -> bool {
+            self.0
+        }
+    }
+
+    fn fixture(name: &str) -> SmallRequest {
+        let contents = fs::read_to_string(
+            env!("CARGO_MANIFEST_DIR").to_string() + "/../movegen/tests/" + name + ".json",
+        )
+        .expect("Something went wrong reading the file");
+        let request: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
+        request.into_small()
+    }
+
+    fn cell(x: i32, y: i32) -> u128 {
+        u128::from(Coordinate { x, y })
+    }
+
+    #[test]
+    fn voronoi_tie_test() {
+        // every cell of the middle column is as far from both heads
+        let column = (0..7).fold(0, |acc, y| acc | cell(3, y));
+        let equal = position("7x7;y 100 1,3 0,3;s 100 5,3 6,3").territory();
+        assert_eq!((equal[0] | equal[1]) & column, 0);
+        assert_eq!(equal[0].count_ones(), equal[1].count_ones());
+        // one segment longer wins every tie
+        let longer = position("7x7;y 100 1,3 0,3 0,2;s 100 5,3 6,3").territory();
+        assert_eq!(longer[0] & column, column);
+        assert_eq!(longer[1] & column, 0);
+    }
+
+    #[test]
+    fn voronoi_tail_test() {
+        // coiled up with the tail next to the head, the middle is only reached once the tail moves off
+        let coiled = position("3x3;y 100 0,0 1,0 2,0 2,1 2,2 1,2 0,2 0,1").territory();
+        assert_eq!(coiled[0] & cell(1, 1), cell(1, 1));
+        assert_eq!(coiled[0].count_ones(), 9);
+        // another snake's tail is gone by the time you get there, its neck isn't
+        let behind = position("5x1;y 100 0,0;s 100 4,0 3,0 2,0").territory();
+        assert_eq!(behind[0], cell(0, 0) | cell(1, 0) | cell(2, 0));
+        assert_eq!(behind[1], cell(4, 0) | cell(3, 0));
+    }
+
+    #[test]
+    fn weights_parse_test() {
+        // missing weights keep their defaults
+        let toml = EvalWeights::from_toml("territory = 3\nhazard = 7").unwrap();
+        let json = EvalWeights::from_json(r#"{"territory": 3, "hazard": 7}"#).unwrap();
+        let expected = EvalWeights {
+            territory: 3,
+            hazard: 7,
+            ..Default::default()
+        };
+        assert_eq!(toml, expected);
+        assert_eq!(json, expected);
+        assert_eq!(EvalWeights::from_toml("").unwrap(), EvalWeights::default());
+        // a typo is an error rather than a weight silently left alone
+        assert!(EvalWeights::from_toml("teritory = 3").is_err());
+        assert!(EvalWeights::from_json(r#"{"teritory": 3}"#).is_err());
+        assert!(EvalWeights::from_toml("territory = \"3\"").is_err());
+    }
+
+    #[test]
+    fn weights_load_test() {
+        // the only test that touches these variables, so nothing races it
+        let dir = env::temp_dir().join(format!("shrimp_weights_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let toml = dir.join("weights.toml");
+        let json = dir.join("weights.json");
+        fs::write(&toml, "territory = 3").unwrap();
+        fs::write(&json, r#"{"territory": 4}"#).unwrap();
+        assert_eq!(EvalWeights::from_file(&toml).unwrap().territory, 3);
+        assert_eq!(EvalWeights::from_file(&json).unwrap().territory, 4);
+        assert!(EvalWeights::from_file(dir.join("missing.toml")).is_err());
+
+        env::remove_var(WEIGHTS_FILE_VAR);
+        assert_eq!(EvalWeights::load().unwrap(), EvalWeights::default());
+        env::set_var(WEIGHTS_FILE_VAR, &toml);
+        env::set_var(WEIGHT_VAR_PREFIX.to_string() + "HAZARD", " 9 ");
+        let loaded = EvalWeights::load().unwrap();
+        assert_eq!(loaded.territory, 3);
+        assert_eq!(loaded.hazard, 9);
+        assert_eq!(loaded.length, EvalWeights::default().length);
+        // single weights win over the file
+        env::set_var(WEIGHT_VAR_PREFIX.to_string() + "TERRITORY", "-2");
+        assert_eq!(EvalWeights::load().unwrap().territory, -2);
+        env::set_var(WEIGHT_VAR_PREFIX.to_string() + "TERRITORY", "lots");
+        assert!(EvalWeights::load().is_err());
+
+        env::remove_var(WEIGHTS_FILE_VAR);
+        env::remove_var(WEIGHT_VAR_PREFIX.to_string() + "HAZARD");
+        env::remove_var(WEIGHT_VAR_PREFIX.to_string() + "TERRITORY");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn food_distance_test() {
+        // food of your own, not clearly the longest so the distance counts twice
+        let own = position("7x7;f 2,3;y 80 1,3 0,3;s 100 5,3 6,3").features()[0];
+        assert_eq!(own.food_distance, 2);
+        // with plenty to spare it isn't urgent
+        assert_eq!(own.food_urgency, 0);
+        // clearly the longest, it counts once
+        let longest = position("7x7;f 2,3;y 80 1,3 0,3 0,2 0,1;s 100 5,3 6,3").features()[0];
+        assert_eq!(longest.food_distance, 1);
+        // food right between two equal heads belongs to neither, but it is still worth going for
+        let tied = position("7x7;f 3,3;y 100 1,3 0,3;s 100 5,3 6,3").features();
+        assert_eq!(tied[0].food_distance, 2 * 2 * 2);
+        assert_eq!(tied[1].food_distance, 2 * 2 * 2);
+        // food someone else gets to first is as good as none
+        let theirs = position("7x7;f 5,4;y 100 1,3 0,3;s 100 5,3 6,3").features()[0];
+        assert_eq!(theirs.food_distance, 2 * 101);
+    }
+
+    #[test]
+    fn food_urgency_test() {
+        // the food is 4 moves away
+        let urgency = |spare: i32| {
+            position(&format!("7x7;f 5,3;y {} 1,3 0,3 0,2", spare + 4)).features()[0].food_urgency
+        };
+        assert_eq!(urgency(60), 0);
+        assert_eq!(urgency(20), 0);
+        // each turn of health less to spare costs more than the one before
+        let mut last_step = 0;
+        for spare in (0..20).rev() {
+            let step = urgency(spare) - urgency(spare + 1);
+            assert!(step > last_step, "{} to spare", spare);
+            last_step = step;
+        }
+        // not making it at all is as bad as it gets
+        assert_eq!(urgency(-3), urgency(0));
+    }
+
+    #[test]
+    fn head_to_head_test() {
+        // both heads can go to 4,3, which the longer one wins
+        let near = position("7x7;y 100 3,3 2,3 1,3;s 100 5,3 6,3").features();
+        // the shorter snake has two other ways out
+        assert_eq!(near[0].aggression, 2);
+        assert_eq!(near[0].exposure, 0);
+        assert_eq!(near[1].aggression, 0);
+        assert_eq!(near[1].exposure, 2);
+        // up against the wall both its moves can meet your head, and it has no other way out
+        let wall = position("7x7;y 100 5,1 4,1 3,1;s 100 6,2 6,3").features();
+        assert_eq!(wall[0].aggression, 2 * 4);
+        assert_eq!(wall[1].exposure, 2 * 4);
+        // nothing to meet
+        let far = position("7x7;y 100 1,1 0,1 0,0;s 100 5,5 6,5").features();
+        assert_eq!((far[0].aggression, far[0].exposure), (0, 0));
+        assert_eq!((far[1].aggression, far[1].exposure), (0, 0));
+    }
+
+    #[test]
+    fn trap_test() {
+        // the corner is a dead end, another snake's body stays next to it for longer than you are long
+        let corner = position("7x7;y 100 1,0 2,0 3,0;s 100 0,1 0,2 0,3 0,4 0,5");
+        let left = Move::new(Direction::Left, 0);
+        assert_eq!(corner.room_after(left), 1);
+        assert!(corner.is_trap(left));
+        assert!(!corner.is_trap(Move::new(Direction::Up, 0)));
+        let allowed: Vec<Direction> = corner.veto_traps(0).iter().map(|x| x.direction).collect();
+        assert_eq!(allowed, vec![Direction::Up]);
+        assert_eq!(&corner.trapped_snakes()[..2], &[false, false]);
+
+        // with the way up shut as well every move is a dead end, so the best of them is kept
+        let sealed = position("7x7;y 100 1,0 2,0 3,0;s 100 2,3 2,2 2,1 1,1 0,1 0,2 0,3 0,4");
+        let allowed: Vec<Direction> = sealed.veto_traps(0).iter().map(|x| x.direction).collect();
+        assert_eq!(allowed, vec![Direction::Left]);
+        assert_eq!(&sealed.trapped_snakes()[..2], &[true, false]);
+        let features = sealed.features();
+        assert_eq!((features[0].trapped, features[0].sealed), (1, 0));
+        assert_eq!((features[1].trapped, features[1].sealed), (0, 1));
+
+        // following your own tail round is no dead end
+        let coiled = position("3x3;y 100 0,0 1,0 2,0 2,1 2,2 1,2 0,2 0,1");
+        assert!(!coiled.trapped_snakes()[0]);
+    }
+
+    #[test]
+    fn eval_all_test() {
+        let weights = EvalWeights::default();
+        for name in ["body", "food", "four_player", "head", "movegen", "wall"] {
+            let mut state = fixture(name);
+            let all = state.eval_all(&weights);
+            // every snake gets the score it would get as you
+            for (id, score) in all.iter().enumerate().take(state.board.snakes.len()) {
+                state.you = id;
+                assert_eq!(*score, state.static_eval(&weights), "{} snake {}", name, id);
+            }
+            assert!(all[state.board.snakes.len()..].iter().all(|x| *x == LOST));
+        }
+        // dead snakes are scored as lost
+        let mut state = position("7x7;y 100 1,3 0,3;s 100 5,3 6,3 6,2 6,1");
+        state.board.snakes[1].alive = false;
+        let all = state.eval_all(&weights);
+        assert_eq!(all[1], LOST);
+        assert_eq!(all[0], state.static_eval(&weights));
+    }
+
+    #[test]
+    fn explain_test() {
+        let evaluator = StandardEval::default();
+        let fields = serde_json::to_value(&evaluator.weights).unwrap();
+        for name in ["body", "food", "four_player", "head", "movegen", "wall"] {
+            let state = fixture(name);
+            let explanation = evaluator.explain(&state);
+            assert_eq!(
+                explanation.total,
+                state.static_eval(&evaluator.weights),
+                "{}",
+                name
+            );
+            assert_eq!(explanation.total, evaluator.eval(&state), "{}", name);
+            // every term is named after the weight it got
+            for term in &explanation.terms {
+                assert_eq!(fields[term.name], term.weight, "{}", term.name);
+            }
+        }
+        // solo games are scored on survival alone
+        let solo = position("7x7;r solo;f 3,3;y 60 1,1 1,2 1,3");
+        let explanation = evaluator.explain(&solo);
+        assert_eq!(explanation.terms.len(), 1);
+        assert_eq!(explanation.terms[0].name, "survival");
+        assert_eq!(explanation.total, evaluator.eval(&solo));
+        assert_eq!(explanation.total, solo.survival_eval(&evaluator.weights));
+        // nothing to explain once you are dead
+        let mut dead = position("7x7;y 100 1,3 0,3;s 100 5,3 6,3");
+        dead.board.snakes[0].alive = false;
+        assert_eq!(evaluator.explain(&dead).total, evaluator.eval(&dead));
+        assert_eq!(evaluator.explain(&dead).total, LOST);
+    }
+
+    #[test]
+    fn health_cost_test() {
+        // 5x3, from the bottom left to the bottom right with hazards across the middle column
+        let passable = (0..5).fold(0, |acc, x| acc | cell(x, 0) | cell(x, 1) | cell(x, 2));
+        let (from, to) = (cell(0, 0), cell(4, 0));
+        let once = [cell(2, 0) | cell(2, 1)];
+        // straight through is 4 moves and a hazard, round the top is 8 moves
+        assert_eq!(health_cost(from, to, passable, &once, 1, 100), Some(5));
+        assert_eq!(health_cost(from, to, passable, &once, 14, 100), Some(8));
+        assert_eq!(health_cost(from, to, passable, &once, 14, 7), None);
+        // stacked hazards hurt once each, so one hazard on a longer way beats two on the shortest
+        let stacked = [
+            cell(2, 0) | cell(2, 1) | cell(2, 2),
+            cell(2, 0) | cell(2, 2),
+        ];
+        assert_eq!(
+            health_cost(from, to, passable, &stacked, 3, 100),
+            Some(6 + 3)
+        );
+        // food cancels out the hazard it is on
+        assert_eq!(
+            health_cost(cell(1, 0), cell(2, 0), passable, &stacked, 14, 100),
+            Some(1)
+        );
+        // nothing gets past a wall
+        let wall = passable & !once[0] & !cell(2, 2);
+        assert_eq!(health_cost(from, to, wall, &[], 0, 100), None);
+    }
+
+    #[test]
+    fn hazard_test() {
+        let column = "h 3,0 3,1 3,2 3,3 3,4 3,5 3,6";
+        let healthy = position(&format!("7x7;{};f 5,3;y 100 1,3 0,3 0,2", column)).features()[0];
+        assert_eq!(healthy.territory, 7 * 7 - 7);
+        assert_eq!(healthy.hazard, 7);
+        // crossing the hazards on the way to the food costs the damage on top of the moves
+        assert_eq!(healthy.food_distance, 4 + 14);
+        // stacked hazards count once each
+        let stacked = position(&format!("7x7;{} 3,3;y 100 1,3 0,3 0,2", column)).features()[0];
+        assert_eq!(stacked.hazard, 8);
+        // too low on health to cross, the other side is as good as gone, and so is the rest of your body
+        let low = position(&format!("7x7;{};f 5,3;y 20 1,3 0,3 0,2", column)).features()[0];
+        assert_eq!(low.territory, 3 * 7 - 2);
+        assert_eq!(low.hazard, 0);
+        assert_eq!(low.food_distance, 101);
+
+        // food behind a hazard is still better than no food at all
+        let weights = EvalWeights::default();
+        let food = format!("7x7;{};f 5,3;y 100 1,3 0,3 0,2 0,1;s 100 6,6 6,5", column);
+        let none = format!("7x7;{};y 100 1,3 0,3 0,2 0,1;s 100 6,6 6,5", column);
+        assert!(position(&food).static_eval(&weights) > position(&none).static_eval(&weights));
+    }
+
+    #[test]
+    fn weighted_sum_test() {
+        let state = position("7x7;y 100 1,3 0,3;s 100 5,3 6,3");
+        let length = |x: &SmallRequest| x.board.snakes[x.you].length as i32;
+        let health = |x: &SmallRequest| x.board.snakes[x.you].health as i32;
+        let sum = WeightedSum::new().with(3, length).with(-1, health);
+        assert_eq!(sum.eval(&state), 3 * 2 - 100);
+        // a weight of 0 switches a part off
+        let off = WeightedSum::new().with(1, length).with(0, health);
+        assert_eq!(off.eval(&state), 2);
+        assert_eq!(WeightedSum::new().eval(&state), 0);
+        // adding up doesn't overflow
+        let big = WeightedSum::new().with(2, |_: &SmallRequest| i32::MAX);
+        assert_eq!(big.eval(&state), i32::MAX);
+        let small = WeightedSum::new().with(-2, |_: &SmallRequest| i32::MAX);
+        assert_eq!(small.eval(&state), i32::MIN);
+    }
+
+    #[test]
+    fn weighted_sum_prune_test() {
+        // distant snakes can only be pruned when every part is fine with it
+        assert!(WeightedSum::new()
+            .with(1, Pruning(true))
+            .with(1, Pruning(true))
+            .prune_distant());
+        assert!(!WeightedSum::new()
+            .with(1, Pruning(true))
+            .with(1, Pruning(false))
+            .prune_distant());
+        assert!(!WeightedSum::new()
+            .with(1, StandardEval::default())
+            .with(1, |_: &SmallRequest| 0)
+            .prune_distant());
+        let sum = WeightedSum::new()
+            .with(1, Pruning(true))
+            .with(5, Pruning(false));
+        assert_eq!(
+            sum.eval(&position("7x7;y 100 1,3 0,3;s 100 5,3 6,3")),
+            1 + 5
+        );
+    }
+}
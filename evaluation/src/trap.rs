@@ -0,0 +1,87 @@
+use board::{
+    board::Coordinate,
+    small::{SmallRequest, SNAKE_MAX},
+};
+use movegen::{genmove::GenMove, makeunmake::Move};
+use tinyvec::ArrayVec;
+
+use crate::{eval::blocked_after, flood::reachable_timed};
+
+/// Cells reachable from a cell the head gets to in one move, with tails leaving as it goes
+fn room(blocked: &[u128], from: Coordinate) -> u32 {
+    let from = u128::from(from);
+    if from == 0 {
+        return 0;
+    }
+    let last = blocked.len() - 1;
+    // the first move is already made, so n more moves is n + 1 from now
+    reachable_timed(
+        from,
+        |step| !blocked[(step as usize + 1).min(last)],
+        last as u32,
+    )
+    .count_ones()
+}
+
+/// Dead ends, where a snake has less room left than its own length
+pub trait Traps {
+    /// Cells the snake can still get to once it made the move
+    fn room_after(&self, mov: Move) -> u32;
+    /// Whether the move leads somewhere with less room than the snake's length
+    fn is_trap(&self, mov: Move) -> bool;
+    /// The snake's moves that don't lead into a dead end, or if they all do, the ones with the most room
+    fn veto_traps(&self, id: usize) -> ArrayVec<[Move; 4]>;
+    /// Whether every move of the snake leads into a dead end, by id
+    fn trapped_snakes(&self) -> [bool; SNAKE_MAX];
+}
+
+impl Traps for SmallRequest {
+    fn room_after(&self, mov: Move) -> u32 {
+        let head = self.board.snakes[mov.id as usize].head;
+        room(&blocked_after(self), head + mov.direction.into())
+    }
+
+    fn is_trap(&self, mov: Move) -> bool {
+        self.room_after(mov) < self.board.snakes[mov.id as usize].length as u32
+    }
+
+    fn veto_traps(&self, id: usize) -> ArrayVec<[Move; 4]> {
+        let blocked = blocked_after(self);
+        let snake = &self.board.snakes[id];
+        let moves = self.snake_moves(id);
+        let rooms: ArrayVec<[u32; 4]> = moves
+            .iter()
+            .map(|x| room(&blocked, snake.head + x.direction.into()))
+            .collect();
+        let best = rooms.iter().copied().max().unwrap_or(0);
+        let needed = best.min(snake.length as u32);
+        moves
+            .iter()
+            .zip(rooms)
+            .filter(|(_, room)| *room >= needed)
+            .map(|(x, _)| *x)
+            .collect()
+    }
+
+    fn trapped_snakes(&self) -> [bool; SNAKE_MAX] {
+        trapped_with(self, &blocked_after(self))
+    }
+}
+
+/// Trapped snakes, given what blocked_after gives
+pub(crate) fn trapped_with(state: &SmallRequest, blocked: &[u128]) -> [bool; SNAKE_MAX] {
+    let mut out = [false; SNAKE_MAX];
+    for snake in &state.board.snakes {
+        if !snake.alive {
+            continue;
+        }
+        let most_room = state
+            .snake_moves(snake.id as usize)
+            .iter()
+            .map(|x| room(blocked, snake.head + x.direction.into()))
+            .max()
+            .unwrap_or(0);
+        out[snake.id as usize] = most_room < snake.length as u32;
+    }
+    out
+}
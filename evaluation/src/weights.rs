@@ -0,0 +1,118 @@
+use std::{env, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+/// Environment variable with the path of a weights file
+pub const WEIGHTS_FILE_VAR: &str = "SHRIMP_WEIGHTS";
+/// Prefix of the environment variables that set single weights, `SHRIMP_WEIGHT_TERRITORY=3` sets territory
+pub const WEIGHT_VAR_PREFIX: &str = "SHRIMP_WEIGHT_";
+
+/// How much each term of the evaluation counts.
+/// Missing weights in a file fall back to the defaults, so a file only needs the ones it changes.
+/// Unknown names are an error, so a typo doesn't go unnoticed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EvalWeights {
+    /// Per body segment you have
+    pub length: i32,
+    /// Per segment you are longer than the longest snake
+    pub length_difference: i32,
+    /// Bonus for being at least as long as every other snake
+    pub biggest: i32,
+    /// Per cell you reach before everyone else
+    pub territory: i32,
+    /// Per segment your territory is short of your length
+    pub cramped: i32,
+    /// Per turn you can expect to survive in solo games
+    pub survival: i32,
+    /// Per move it takes to reach food you get to first, or tie for at twice the moves, taken off
+    pub food_distance: i32,
+    /// Per square of how far the health you have to spare once you reach that food is below 20, taken off
+    pub food_urgency: i32,
+    /// Per point of health
+    pub health: i32,
+    /// Per cell a shorter snake can meet your head in, more the fewer ways out it has
+    pub aggression: i32,
+    /// Per cell an equal or longer snake can meet your head in, more the fewer ways out you have, taken off
+    pub exposure: i32,
+    /// Taken off when every move you have leads somewhere with less room than your length
+    pub trapped: i32,
+    /// Per opponent whose every move leads somewhere with less room than its length
+    pub sealed: i32,
+    /// Per hazard in your territory, stacked ones counting once each, taken off
+    pub hazard: i32,
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        Self {
+            length: 10,
+            length_difference: 20,
+            biggest: 1000,
+            territory: 2,
+            cramped: 50,
+            survival: 10,
+            food_distance: 5,
+            food_urgency: 1,
+            health: 1,
+            aggression: 30,
+            exposure: 40,
+            trapped: 2000,
+            sealed: 1000,
+            hazard: 1,
+        }
+    }
+}
+
+impl EvalWeights {
+    /// Parse weights out of toml
+    pub fn from_toml(input: &str) -> Result<Self, String> {
+        toml::from_str(input).map_err(|x| x.to_string())
+    }
+
+    /// Parse weights out of json
+    pub fn from_json(input: &str) -> Result<Self, String> {
+        serde_json::from_str(input).map_err(|x| x.to_string())
+    }
+
+    /// Read a weights file, json if it ends in .json and toml otherwise
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .map_err(|x| format!("couldn't read {}: {}", path.display(), x))?;
+        if path.extension().is_some_and(|x| x == "json") {
+            Self::from_json(&contents)
+        } else {
+            Self::from_toml(&contents)
+        }
+    }
+
+    /// Set single weights from `SHRIMP_WEIGHT_<NAME>` variables, the name being the field in upper case
+    pub fn with_env_overrides(self) -> Result<Self, String> {
+        let mut fields = match serde_json::to_value(&self).map_err(|x| x.to_string())? {
+            serde_json::Value::Object(fields) => fields,
+            _ => unreachable!("weights serialize to a map"),
+        };
+        for (name, value) in fields.iter_mut() {
+            let var = WEIGHT_VAR_PREFIX.to_string() + &name.to_uppercase();
+            if let Ok(input) = env::var(&var) {
+                let weight: i32 = input
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("{} isn't a whole number: {}", var, input))?;
+                *value = weight.into();
+            }
+        }
+        serde_json::from_value(serde_json::Value::Object(fields)).map_err(|x| x.to_string())
+    }
+
+    /// The weights to start the engine with: the file named by `SHRIMP_WEIGHTS` if there is one,
+    /// otherwise the defaults, and then any single weights set in the environment
+    pub fn load() -> Result<Self, String> {
+        let weights = match env::var(WEIGHTS_FILE_VAR) {
+            Ok(path) => Self::from_file(path)?,
+            Err(_) => Self::default(),
+        };
+        weights.with_env_overrides()
+    }
+}
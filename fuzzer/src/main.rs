@@ -1,3 +1,32 @@
+use std::{env, fs, process};
+
+use board::board::GameRequest;
+use movegen::conformance::check_game;
+
+/// Replays every game log given on the command line and prints where make_move disagrees with it.
+/// A log has one game request per line, one line per turn.
 fn main() {
-    println!("Hello, world!");
+    let mut failed = false;
+    for path in env::args().skip(1) {
+        let contents = fs::read_to_string(&path).expect("Something went wrong reading the file");
+        let states: Vec<GameRequest> = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).expect("Invalid json"))
+            .collect();
+        let mismatches = check_game(&states);
+        for mismatch in &mismatches {
+            println!("{}: {}", path, mismatch);
+        }
+        println!(
+            "{}: {} turns, {} mismatches",
+            path,
+            states.len(),
+            mismatches.len()
+        );
+        failed |= !mismatches.is_empty();
+    }
+    if failed {
+        process::exit(1);
+    }
 }
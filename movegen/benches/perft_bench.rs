@@ -1,65 +1,36 @@
 use std::fs;
 
-use board::{board::GameRequest, small::SmallRequest};
+use board::board::GameRequest;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use movegen::{
+    bitmove::BitMoves,
     genmove::GenMove,
     makeunmake::{Direction, MakeUnmake, Move},
+    perft,
 };
-// use criterion::{black_box, criterion_group, criterion_main, Criterion};
-
-fn perft(state: &mut SmallRequest, depth: u8, player: bool, you_move: Option<Move>) {
-    if state.amount_alive() as usize == 0
-        || !state.board.snakes[state.you].alive
-        || state.amount_alive() as usize == 1
-        || depth == 0
-    {
-        // im the only one left
-        return;
-    }
-    if player {
-        for x in state.snake_moves(state.you) {
-            perft(state, depth, !player, Some(x));
-        }
-    } else {
-        let mut delta;
-        for x in state.all_snake_moves(you_move.unwrap()) {
-            // let t0 = state.clone();
-            delta = state.make_move(&x);
-            perft(state, depth - 1, !player, None);
-            state.unmake_move(&delta);
-            // assert_eq!(state.board.snakes, t0.board.snakes);
-        }
-    }
-}
 
 fn movegen_perft_midgame(c: &mut Criterion) {
     let contents =
-        fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "\\tests\\movegen.json")
+        fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/movegen.json")
             .expect("Something went wrong reading the file");
     // make it into a smallrequest
     let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
     let mut small = seralized.into_small();
-    c.bench_function("movegen_perft_midgame", |b| {
-        b.iter(|| perft(&mut small, 5, true, None))
-    });
+    c.bench_function("movegen_perft_midgame", |b| b.iter(|| perft(&mut small, 5)));
 }
 
 fn movegen_perft_earlygame(c: &mut Criterion) {
-    let contents =
-        fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "\\tests\\food.json")
-            .expect("Something went wrong reading the file");
+    let contents = fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/food.json")
+        .expect("Something went wrong reading the file");
     // make it into a smallrequest
     let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
     let mut small = seralized.into_small();
-    c.bench_function("movegen_perft_early", |b| {
-        b.iter(|| perft(&mut small, 5, true, None))
-    });
+    c.bench_function("movegen_perft_early", |b| b.iter(|| perft(&mut small, 5)));
 }
 
 fn move_make_midgame(c: &mut Criterion) {
     let contents =
-        fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "\\tests\\movegen.json")
+        fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/movegen.json")
             .expect("Something went wrong reading the file");
     // make it into a smallrequest
     let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
@@ -75,7 +46,7 @@ fn move_make_midgame(c: &mut Criterion) {
 
 fn movegen_midgame(c: &mut Criterion) {
     let contents =
-        fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "\\tests\\movegen.json")
+        fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/movegen.json")
             .expect("Something went wrong reading the file");
     // make it into a smallrequest
     let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
@@ -89,9 +60,20 @@ fn movegen_midgame(c: &mut Criterion) {
         })
     });
 }
+fn move_masks_midgame(c: &mut Criterion) {
+    let contents =
+        fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/four_player.json")
+            .expect("Something went wrong reading the file");
+    // make it into a smallrequest
+    let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
+    let small = seralized.into_small();
+    c.bench_function("move_masks_4_player", |b| {
+        b.iter(|| black_box(&small).move_masks())
+    });
+}
 fn make_all_moves(c: &mut Criterion) {
     let contents =
-        fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "\\tests\\four_player.json")
+        fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/four_player.json")
             .expect("Something went wrong reading the file");
     // make it into a smallrequest
     let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
@@ -113,5 +95,6 @@ criterion_group!(
     movegen_perft_earlygame,
     move_make_midgame,
     movegen_midgame,
+    move_masks_midgame,
 );
 criterion_main!(benches);
@@ -0,0 +1,51 @@
+use std::{env, fs, path::Path, process, time::Instant};
+
+use board::{board::GameRequest, notation::Notation, small::SmallRequest};
+use movegen::{divide, perft};
+
+/// Runs perft on a position.
+/// `perft <position> <depth> [divide]` where the position is a game request json file or notation.
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        eprintln!("usage: perft <json file | notation> <depth> [divide]");
+        process::exit(2);
+    }
+    let mut state = if Path::new(&args[1]).is_file() {
+        let contents = fs::read_to_string(&args[1]).expect("Something went wrong reading the file");
+        let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
+        seralized.into_small()
+    } else {
+        SmallRequest::from_notation(&args[1]).unwrap_or_else(|err| {
+            eprintln!("bad position: {}", err);
+            process::exit(2);
+        })
+    };
+    let depth: u8 = args[2].parse().expect("depth should be a number");
+
+    let t0 = Instant::now();
+    if args.get(3).map(|x| x.as_str()) == Some("divide") {
+        let entries = divide(&mut state, depth);
+        let mut total = 0;
+        let mut you_total = 0;
+        for (n, entry) in entries.iter().enumerate() {
+            let moves: Vec<String> = entry
+                .joint_move
+                .iter()
+                .map(|x| format!("{}:{}", x.id, x.direction.to_string()))
+                .collect();
+            println!("  {} {}", moves.join(" "), entry.nodes);
+            total += entry.nodes;
+            you_total += entry.nodes;
+            // subtotal once the last joint move for this move of yours is done
+            if entries.get(n + 1).map(|x| x.you_move) != Some(entry.you_move) {
+                println!("{} {}", entry.you_move.direction.to_string(), you_total);
+                you_total = 0;
+            }
+        }
+        println!("total {}", total);
+    } else {
+        println!("total {}", perft(&mut state, depth));
+    }
+    println!("took {:?}", Instant::now() - t0);
+}
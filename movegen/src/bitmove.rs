@@ -0,0 +1,89 @@
+use board::{
+    bitboard::{board_mask, shift_down, shift_left, shift_right, shift_up},
+    small::{SmallRequest, SNAKE_MAX},
+};
+use tinyvec::{array_vec, ArrayVec};
+
+use crate::makeunmake::{Direction, Move};
+
+/// Bit for up in a direction mask
+pub const UP: u8 = 1;
+/// Bit for down in a direction mask
+pub const DOWN: u8 = 1 << 1;
+/// Bit for left in a direction mask
+pub const LEFT: u8 = 1 << 2;
+/// Bit for right in a direction mask
+pub const RIGHT: u8 = 1 << 3;
+
+/// Move generation for every snake at once, done on bitboards
+pub trait BitMoves {
+    /// A 4 bit direction mask for every snake slot, with the same moves snake_moves gives.
+    /// Dead and missing snakes get an empty mask.
+    fn move_masks(&self) -> [u8; SNAKE_MAX];
+}
+
+/// Turn a direction mask back into moves for the snake with the given id
+pub fn mask_moves(mask: u8, id: u8) -> ArrayVec<[Move; 4]> {
+    let mut out = array_vec![];
+    for (bit, direction) in [
+        (UP, Direction::Up),
+        (RIGHT, Direction::Right),
+        (LEFT, Direction::Left),
+        (DOWN, Direction::Down),
+    ] {
+        if mask & bit != 0 {
+            out.push(Move::new(direction, id));
+        }
+    }
+    out
+}
+
+impl BitMoves for SmallRequest {
+    fn move_masks(&self) -> [u8; SNAKE_MAX] {
+        let mut out = [0; SNAKE_MAX];
+
+        // every cell that is taken next turn, tails move out of the way unless they were just grown onto
+        let mut blocked = 0;
+        for snake in &self.board.snakes {
+            if !snake.alive {
+                continue;
+            }
+            let len = snake.body.len();
+            let tail = snake.body[len - 1];
+            let mut taken = snake.body_bb | snake.head_bb;
+            if len < 2 || snake.body[len - 2] != tail {
+                taken &= !u128::from(tail);
+            }
+            blocked |= taken;
+        }
+        if self.rules.map.hazards_are_walls() {
+            blocked |= self.board.hazard_bb;
+        }
+        let free = board_mask(self.board.width, self.board.height) & !blocked;
+
+        for snake in &self.board.snakes {
+            if !snake.alive {
+                continue;
+            }
+            // going back into the neck isn't a move, even when the neck is a tail about to move
+            let mut allowed = free;
+            if let Some(neck) = snake.body.get(1) {
+                if *neck != snake.head {
+                    allowed &= !u128::from(*neck);
+                }
+            }
+            let head = snake.head_bb;
+            for (bit, destination) in [
+                (UP, shift_up(head)),
+                (DOWN, shift_down(head)),
+                (LEFT, shift_left(head)),
+                (RIGHT, shift_right(head)),
+            ] {
+                if destination & allowed != 0 {
+                    out[snake.id as usize] |= bit;
+                }
+            }
+        }
+        out
+    }
+}
@@ -3,44 +3,72 @@ use tinyvec::{array_vec, ArrayVec};
 
 use crate::makeunmake::Move;
 
-// excerpted from : https://gist.github.com/kylewlacy/115965b40e02a3325558
-/// Given a vector containing a partial Cartesian product, and a list of items,
-/// return a vector adding the list of items to the partial Cartesian product.
-pub fn partial_cartesian(
-    a: ArrayVec<[ArrayVec<[Move; SNAKE_MAX]>; (4usize).pow(SNAKE_MAX as u32)]>,
-    b: tinyvec::ArrayVec<[Move; 4]>,
-) -> ArrayVec<[ArrayVec<[Move; SNAKE_MAX]>; (4usize).pow(SNAKE_MAX as u32)]> {
-    a.into_iter()
-        .flat_map(|xs| {
-            b.iter()
-                .cloned()
-                .map(|y| {
-                    let mut vec = xs;
-                    vec.push(y);
-                    vec
-                })
-                .collect::<Vec<_>>()
-        })
-        .collect()
+/// Lazily walks the Cartesian product of every snake's moves, one joint move at a time.
+/// Nothing is allocated, and stopping early never builds the rest.
+#[derive(Clone, Debug)]
+pub struct JointMoves {
+    /// Moves for each snake
+    lists: ArrayVec<[ArrayVec<[Move; 4]>; SNAKE_MAX]>,
+    /// Which move of each list the next joint move uses
+    indices: [usize; SNAKE_MAX],
+    /// Whether every combination has been given out
+    done: bool,
 }
 
-/// Computes the Cartesian product of lists[0] * lists[1] * ... * lists[n].
-pub fn cartesian_product(
-    lists: ArrayVec<[ArrayVec<[Move; 4]>; SNAKE_MAX]>,
-) -> ArrayVec<[ArrayVec<[Move; SNAKE_MAX]>; (4usize).pow(SNAKE_MAX as u32)]> {
-    match lists.split_first() {
-        Some((first, rest)) => {
-            let init: ArrayVec<[ArrayVec<[Move; SNAKE_MAX]>; (4usize).pow(SNAKE_MAX as u32)]> =
-                first
-                    .iter()
-                    .cloned()
-                    .map(|n| array_vec!([Move; SNAKE_MAX] => n))
-                    .collect();
+impl JointMoves {
+    /// Make the iterator from each snake's moves, if any snake has no moves there are no joint moves
+    pub fn new(lists: ArrayVec<[ArrayVec<[Move; 4]>; SNAKE_MAX]>) -> Self {
+        let done = lists.is_empty() || lists.iter().any(|x| x.is_empty());
+        Self {
+            lists,
+            indices: [0; SNAKE_MAX],
+            done,
+        }
+    }
+
+    /// Sort each snake's moves by the key, smallest first.
+    /// The first joint move is then made of every snake's best move, so a cutoff comes sooner.
+    pub fn ordered_by<K: Ord, F: FnMut(&Move) -> K>(mut self, mut key: F) -> Self {
+        for list in &mut self.lists {
+            list.sort_unstable_by_key(&mut key);
+        }
+        self
+    }
 
-            rest.iter().cloned().fold(init, partial_cartesian)
+    /// Amount of joint moves in total, including ones that were already given out
+    pub fn total(&self) -> usize {
+        if self.lists.iter().any(|x| x.is_empty()) {
+            return 0;
+        }
+        self.lists.iter().map(|x| x.len()).product()
+    }
+}
+
+impl Iterator for JointMoves {
+    type Item = ArrayVec<[Move; SNAKE_MAX]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut out = array_vec![];
+        for (list, index) in self.lists.iter().zip(self.indices) {
+            out.push(list[index]);
         }
-        None => {
-            array_vec!()
+        // count up like an odometer, the last snake turning over fastest
+        let mut n = self.lists.len();
+        loop {
+            if n == 0 {
+                self.done = true;
+                break;
+            }
+            n -= 1;
+            self.indices[n] += 1;
+            if self.indices[n] < self.lists[n].len() {
+                break;
+            }
+            self.indices[n] = 0;
         }
+        Some(out)
     }
 }
@@ -0,0 +1,180 @@
+use std::fmt::{self, Display};
+
+use board::{
+    board::GameRequest,
+    small::{SmallRequest, SNAKE_MAX},
+};
+use tinyvec::{array_vec, ArrayVec};
+
+use crate::{
+    makeunmake::{Direction, MakeUnmake, Move},
+    tracker::direction_between,
+};
+
+/// Something the simulation did differently from the recorded game
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Turn the moves were made on
+    pub turn: i64,
+    /// Id of the snake that differs, None if it is about the whole board
+    pub snake: Option<String>,
+    /// What went wrong
+    pub reason: String,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.snake {
+            Some(snake) => write!(f, "turn {}, snake {}: {}", self.turn, snake, self.reason),
+            None => write!(f, "turn {}: {}", self.turn, self.reason),
+        }
+    }
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+/// Replay a recorded game, one request per turn, through make_move and report everything that doesn't line up.
+/// Snakes that disappear could have moved anywhere, so every direction is tried for them.
+pub fn check_game(states: &[GameRequest]) -> Vec<Mismatch> {
+    let mut out = vec![];
+    for pair in states.windows(2) {
+        out.append(&mut check_turn(&pair[0], &pair[1]));
+    }
+    out
+}
+
+/// Check a single turn of a recorded game
+pub fn check_turn(prev: &GameRequest, next: &GameRequest) -> Vec<Mismatch> {
+    let turn = prev.turn;
+    if next.turn != turn + 1 {
+        return vec![Mismatch {
+            turn,
+            snake: None,
+            reason: format!("next recorded turn is {}", next.turn),
+        }];
+    }
+    let state = prev.into_small();
+    let prev_ids = prev.snake_ids();
+    let next_state = next.into_small();
+    let next_ids = next.snake_ids();
+
+    // work out the moves of the snakes that are still around
+    let mut known: ArrayVec<[Move; SNAKE_MAX]> = array_vec![];
+    let mut vanished: ArrayVec<[u8; SNAKE_MAX]> = array_vec![];
+    for (id, name) in prev_ids.iter().enumerate() {
+        match next_ids.iter().position(|x| x == name) {
+            Some(next_id) => {
+                let from = state.board.snakes[id].head;
+                let to = next_state.board.snakes[next_id].head;
+                match direction_between(from, to) {
+                    Some(dir) => known.push(Move::new(dir, id as u8)),
+                    None => {
+                        return vec![Mismatch {
+                            turn,
+                            snake: Some(name.to_string()),
+                            reason: format!("head went from {:?} to {:?}", from, to),
+                        }]
+                    }
+                }
+            }
+            None => vanished.push(id as u8),
+        }
+    }
+
+    // try every way the vanished snakes could have moved, keeping the closest match
+    let mut best: Option<Vec<Mismatch>> = None;
+    for combination in 0..4usize.pow(vanished.len() as u32) {
+        let mut moves = known;
+        for (n, id) in vanished.iter().enumerate() {
+            moves.push(Move::new(DIRECTIONS[(combination >> (2 * n)) & 3], *id));
+        }
+        let mut simulated = state.clone();
+        simulated.make_move(&moves);
+        let mismatches = compare(turn, &simulated, &prev_ids, &next_state, &next_ids);
+        if best.as_ref().is_none_or(|x| mismatches.len() < x.len()) {
+            best = Some(mismatches);
+        }
+        if best.as_ref().is_some_and(|x| x.is_empty()) {
+            break;
+        }
+    }
+    best.unwrap_or_default()
+}
+
+/// Compare a simulated state against the recorded one.
+/// Extra food in the recording is fine as long as it is somewhere food could have spawned.
+fn compare(
+    turn: i64,
+    simulated: &SmallRequest,
+    simulated_ids: &[&str],
+    recorded: &SmallRequest,
+    recorded_ids: &[&str],
+) -> Vec<Mismatch> {
+    let mut out = vec![];
+    let mut occupied = 0;
+    for snake in &simulated.board.snakes {
+        let name = simulated_ids[snake.id as usize];
+        let mismatch = |reason: String| Mismatch {
+            turn,
+            snake: Some(name.to_string()),
+            reason,
+        };
+        let other = recorded_ids
+            .iter()
+            .position(|x| *x == name)
+            .map(|x| &recorded.board.snakes[x]);
+        match (snake.alive, other) {
+            (true, None) => out.push(mismatch("survived but was eliminated".to_string())),
+            (false, Some(_)) => out.push(mismatch("was eliminated but survived".to_string())),
+            (false, None) => {}
+            (true, Some(other)) => {
+                occupied |= snake.body_bb | snake.head_bb;
+                if snake.body != other.body {
+                    out.push(mismatch(format!(
+                        "body {:?}, expected {:?}",
+                        snake.body, other.body
+                    )));
+                }
+                if snake.health != other.health {
+                    out.push(mismatch(format!(
+                        "health {}, expected {}",
+                        snake.health, other.health
+                    )));
+                }
+                if snake.length != other.length {
+                    out.push(mismatch(format!(
+                        "length {}, expected {}",
+                        snake.length, other.length
+                    )));
+                }
+                if snake.body_bb != other.body_bb || snake.head_bb != other.head_bb {
+                    out.push(mismatch("bitboards out of sync with the body".to_string()));
+                }
+            }
+        }
+    }
+    for food in &simulated.board.food {
+        if !recorded.board.food.contains(food) {
+            out.push(Mismatch {
+                turn,
+                snake: None,
+                reason: format!("food at {:?} should have been eaten", food),
+            });
+        }
+    }
+    for food in &recorded.board.food {
+        if !simulated.board.food.contains(food) && u128::from(*food) & occupied != 0 {
+            out.push(Mismatch {
+                turn,
+                snake: None,
+                reason: format!("food at {:?} can't have spawned under a snake", food),
+            });
+        }
+    }
+    out
+}
@@ -0,0 +1,81 @@
+use board::small::{SmallRequest, SNAKE_MAX};
+use tinyvec::ArrayVec;
+
+use crate::makeunmake::{Delta, MakeUnmake, Move};
+
+/// A position along with every joint move made since the root, so they can be taken back or played again
+pub struct Game {
+    /// The position nothing has been played on
+    root: SmallRequest,
+    /// The position after every move in history
+    state: SmallRequest,
+    /// Joint moves made so far, oldest first
+    moves: Vec<ArrayVec<[Move; SNAKE_MAX]>>,
+    /// Deltas matching the moves
+    deltas: Vec<Delta>,
+}
+
+impl Game {
+    /// Start a game from a position
+    pub fn new(root: SmallRequest) -> Self {
+        Self {
+            state: root.clone(),
+            root,
+            moves: vec![],
+            deltas: vec![],
+        }
+    }
+
+    /// Start from a position and play the joint moves on it, in order
+    pub fn replay(root: SmallRequest, moves: &[ArrayVec<[Move; SNAKE_MAX]>]) -> Self {
+        let mut out = Self::new(root);
+        for joint_move in moves {
+            out.push(*joint_move);
+        }
+        out
+    }
+
+    /// Make a joint move on the current position
+    pub fn push(&mut self, joint_move: ArrayVec<[Move; SNAKE_MAX]>) -> &SmallRequest {
+        let delta = self.state.make_move(&joint_move);
+        self.moves.push(joint_move);
+        self.deltas.push(delta);
+        &self.state
+    }
+
+    /// Take back the last joint move and give it back, None if already at the root
+    pub fn pop(&mut self) -> Option<ArrayVec<[Move; SNAKE_MAX]>> {
+        let delta = self.deltas.pop()?;
+        self.state.unmake_move(&delta);
+        self.moves.pop()
+    }
+
+    /// Joint moves made since the root, oldest first
+    pub fn history(&self) -> &[ArrayVec<[Move; SNAKE_MAX]>] {
+        &self.moves
+    }
+
+    /// Amount of joint moves made since the root
+    pub fn ply(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// The current position
+    pub fn state(&self) -> &SmallRequest {
+        &self.state
+    }
+
+    /// The position the game started from
+    pub fn root(&self) -> &SmallRequest {
+        &self.root
+    }
+
+    /// The position after the first ply joint moves, played again from the root
+    pub fn state_at(&self, ply: usize) -> SmallRequest {
+        let mut out = self.root.clone();
+        for joint_move in self.moves.iter().take(ply) {
+            out.make_move(joint_move);
+        }
+        out
+    }
+}
@@ -1,22 +1,113 @@
 use crate::{
-    cart_prod::cartesian_product,
+    bitmove::{mask_moves, BitMoves},
+    cart_prod::JointMoves,
     makeunmake::{Direction, Move},
 };
 use board::{
+    bitboard::neighbours,
     board::Coordinate,
     small::{SmallRequest, SNAKE_MAX},
 };
 use tinyvec::{array_vec, ArrayVec};
+/// How dangerous a move is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Safety {
+    /// Nothing can kill the snake there this turn
+    Safe,
+    /// Might kill the snake, depending on what the others do
+    Risky,
+    /// Kills the snake no matter what
+    Fatal,
+}
+
+/// Why a move is as safe as it is
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MoveReason {
+    /// Nothing is in the way
+    #[default]
+    Clear,
+    /// An equal or longer snake, with this id, can move its head into the same cell
+    HeadToHead(u8),
+    /// The tail of the snake with this id only moves out of the way if it doesn't eat
+    Tail(u8),
+    /// Leaves the board
+    Wall,
+    /// Runs into a hazard on a map where hazards are walls
+    Hazard,
+    /// Runs into the body of the snake with this id
+    Body(u8),
+}
+
+impl MoveReason {
+    /// How dangerous a move with this reason is
+    pub fn safety(&self) -> Safety {
+        match self {
+            MoveReason::Clear => Safety::Safe,
+            MoveReason::HeadToHead(_) | MoveReason::Tail(_) => Safety::Risky,
+            MoveReason::Wall | MoveReason::Hazard | MoveReason::Body(_) => Safety::Fatal,
+        }
+    }
+}
+
+/// Which moves to generate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveMode {
+    /// Every move except going back into the neck
+    Legal,
+    /// Only moves that can't kill the snake
+    Safe,
+    /// Only moves that might kill the snake, depending on what the others do
+    Risky,
+    /// Safe and risky moves together
+    NotFatal,
+}
+
+/// A move along with the reason for how safe it is
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TaggedMove {
+    /// The move itself
+    pub mov: Move,
+    /// Why it is as safe as it is
+    pub reason: MoveReason,
+}
+
 pub trait GenMove {
     /// Get valid moves for a given snake
-    /// By valid that means it doesn't cause an instant death.
+    /// By valid that means it doesn't cause a certain death, head to head collisions that might happen are kept.
     fn snake_moves(&self, id: usize) -> ArrayVec<[Move; 4]>;
+    /// Get the moves for a given snake that fit the mode, along with why they are as safe as they are
+    fn tagged_moves(&self, id: usize, mode: MoveMode) -> ArrayVec<[TaggedMove; 4]>;
     /// Generate a 2D vector of moves for all snakes
     /// This should be given the move predetermined move for the "you" player
     fn all_snake_moves(
         &self,
         predet_move: Move,
     ) -> ArrayVec<[ArrayVec<[Move; SNAKE_MAX]>; 4_usize.pow(SNAKE_MAX as u32)]>;
+    /// Same moves as all_snake_moves, but handed out one at a time without building them all
+    fn joint_moves(&self, predet_move: Move) -> JointMoves;
+    /// Same as joint_moves, but opponents with every cell of their body more than twice depth away from every other head get fewer moves.
+    /// Within depth moves nobody else can meet their head, get to a cell they move onto, or get to a cell they free up by dying,
+    /// so they keep every move onto food, since that changes the food left and what spawns, and one of the rest.
+    /// Only an eval that doesn't look at where those snakes are gets the same result at that depth,
+    /// territory, food races and lengths all do, and so do the cells sampled for food spawns.
+    fn joint_moves_near(&self, predet_move: Move, depth: u8) -> JointMoves;
+    /// Moves for a snake that dies whatever it does.
+    /// It still takes food and meets heads on the way out, so every move that stays on the board is kept.
+    /// If every move leaves the board, the first one is given so make_move eliminates it.
+    fn doomed_moves(&self, id: usize) -> ArrayVec<[Move; 4]>;
+}
+
+/// Cells at most `steps` moves from a set bit, ignoring what is in the way
+fn spread(bb: u128, steps: u32) -> u128 {
+    let mut out = bb;
+    for _ in 0..steps {
+        let next = out | neighbours(out);
+        if next == out {
+            break;
+        }
+        out = next;
+    }
+    out
 }
 
 fn get_neck_dir(head: &Coordinate, neck: &Coordinate) -> Option<Direction> {
@@ -33,64 +124,93 @@ fn get_neck_dir(head: &Coordinate, neck: &Coordinate) -> Option<Direction> {
     }
 }
 
+trait Classify {
+    fn move_reason(&self, id: usize, new_pos: Coordinate) -> MoveReason;
+}
+
+impl Classify for SmallRequest {
+    fn move_reason(&self, id: usize, new_pos: Coordinate) -> MoveReason {
+        let me = &self.board.snakes[id];
+        if new_pos.x >= self.board.width as i32
+            || new_pos.x < 0
+            || new_pos.y >= self.board.height as i32
+            || new_pos.y < 0
+        {
+            return MoveReason::Wall;
+        }
+        let new_bb = u128::from(new_pos);
+        if self.rules.map.hazards_are_walls() && self.board.hazard_bb & new_bb != 0 {
+            return MoveReason::Hazard;
+        }
+        let mut risk = MoveReason::Clear;
+        for snake in &self.board.snakes {
+            if !snake.alive {
+                continue;
+            }
+            let tail = *snake.body.last().unwrap();
+            // a tail that was just grown onto stays where it is
+            let grown = snake.body.len() > 1 && snake.body[snake.body.len() - 2] == tail;
+            let mut blocked = snake.body_bb | snake.head_bb;
+            if !grown {
+                blocked &= !u128::from(tail);
+            }
+            if blocked & new_bb != 0 {
+                return MoveReason::Body(snake.id);
+            }
+            if snake.id == me.id {
+                continue;
+            }
+            // the tail stays put if its snake eats this turn
+            if !grown && tail == new_pos && neighbours(snake.head_bb) & self.board.food_bb != 0 {
+                risk = MoveReason::Tail(snake.id);
+            }
+            if neighbours(snake.head_bb) & new_bb != 0 && snake.length >= me.length {
+                risk = MoveReason::HeadToHead(snake.id);
+            }
+        }
+        risk
+    }
+}
+
 impl GenMove for SmallRequest {
     fn snake_moves(&self, id: usize) -> ArrayVec<[Move; 4]> {
+        self.tagged_moves(id, MoveMode::NotFatal)
+            .iter()
+            .map(|x| x.mov)
+            .collect()
+    }
+
+    fn tagged_moves(&self, id: usize, mode: MoveMode) -> ArrayVec<[TaggedMove; 4]> {
         let mut out = array_vec![];
 
-        if !self.board.snakes[id].alive {
+        let me = &self.board.snakes[id];
+        if !me.alive {
             return out;
         }
-        let possible_moves = array_vec![ [Move;4] =>
-            Move::new(crate::makeunmake::Direction::Up,id as u8),
-            Move::new(crate::makeunmake::Direction::Right,id as u8),
-            Move::new(crate::makeunmake::Direction::Left, id as u8),
-            Move::new(crate::makeunmake::Direction::Down,id as u8),
-        ];
-        for mov in possible_moves.iter() {
-            let new_pos: Coordinate = self.board.snakes[id].head + mov.direction.into();
-            let mut removed = false;
-            let neck_direction =
-                get_neck_dir(&self.board.snakes[id].head, &self.board.snakes[id].body[1]);
-            match neck_direction {
-                Some(neck_direction_actual) => {
-                    if neck_direction_actual == mov.direction {
-                        removed = true;
-                    } else {
-                        for snake in &self.board.snakes {
-                            if !snake.alive {
-                                continue;
-                            } // move on if the other snake is dead
-                            if new_pos.x >= self.board.width as i32
-                                || new_pos.x < 0
-                                || new_pos.y >= self.board.height as i32
-                                || new_pos.y < 0
-                            {
-                                removed = true;
-                                break;
-                            } // remove if the head is out of bounds
-                            if snake.id != mov.id
-                                && snake.head == new_pos
-                                && snake.length >= self.board.snakes[id].length
-                            {
-                                removed = true;
-                                break;
-                            } // remove the move if the head is the same as the new head pos, and the other length is bigger or equal to my length
-                            if (snake.body_bb & !(u128::from(*snake.body.last().unwrap())))
-                                & u128::from(new_pos)
-                                != 0
-                            {
-                                removed = true;
-                                break;
-                            } // remove if the head is in the other snake
-                        }
-                    }
-                }
-                None => {
-                    removed = false;
-                }
+        let neck_direction = me.body.get(1).and_then(|neck| get_neck_dir(&me.head, neck));
+        for direction in [
+            Direction::Up,
+            Direction::Right,
+            Direction::Left,
+            Direction::Down,
+        ] {
+            // going back into the neck is the only move that isn't legal
+            if neck_direction == Some(direction) {
+                continue;
             }
-            if !removed {
-                out.push(*mov);
+            let new_pos: Coordinate = me.head + direction.into();
+            let reason = self.move_reason(id, new_pos);
+            let keep = match mode {
+                MoveMode::Legal => true,
+                MoveMode::Safe => reason.safety() == Safety::Safe,
+                MoveMode::Risky => reason.safety() == Safety::Risky,
+                MoveMode::NotFatal => reason.safety() != Safety::Fatal,
+            };
+            if keep {
+                out.push(TaggedMove {
+                    mov: Move::new(direction, id as u8),
+                    reason,
+                });
             }
         }
         out
@@ -100,19 +220,68 @@ impl GenMove for SmallRequest {
         &self,
         predet_move: Move,
     ) -> ArrayVec<[ArrayVec<[Move; SNAKE_MAX]>; 4_usize.pow(SNAKE_MAX as u32)]> {
-        let mut moves: ArrayVec<[ArrayVec<[Move; SNAKE_MAX]>; SNAKE_MAX]> = array_vec![];
-        for id in 0..self.board.snakes.len() {
-            if id != self.you && self.board.snakes[id].alive {
-                let generated_moves = self.snake_moves(id);
-                if generated_moves.is_empty() {
-                    moves.push(array_vec![[Move ; 4] => Move::new(Direction::Up, id as u8)])
+        self.joint_moves(predet_move).collect()
+    }
+
+    fn joint_moves(&self, predet_move: Move) -> JointMoves {
+        self.joint_moves_near(predet_move, u8::MAX)
+    }
+
+    fn joint_moves_near(&self, predet_move: Move, depth: u8) -> JointMoves {
+        let mut moves: ArrayVec<[ArrayVec<[Move; 4]>; SNAKE_MAX]> = array_vec![];
+        let masks = self.move_masks();
+        // cells within twice depth of each head, anything further away can't be met by it within depth moves
+        let reach: ArrayVec<[u128; SNAKE_MAX]> = self
+            .board
+            .snakes
+            .iter()
+            .map(|x| {
+                if x.alive {
+                    spread(x.head_bb, 2 * depth as u32)
                 } else {
-                    moves.push(generated_moves);
+                    0
+                }
+            })
+            .collect();
+        for (id, snake) in self.board.snakes.iter().enumerate() {
+            if id != self.you && snake.alive {
+                let mut generated_moves = mask_moves(masks[id], id as u8);
+                if generated_moves.is_empty() {
+                    generated_moves = self.doomed_moves(id);
+                }
+                // too far from everyone to make a difference to them within depth, eating still changes what happens to the food
+                let others = reach
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, _)| *other != id)
+                    .fold(0, |acc, (_, x)| acc | x);
+                if (snake.body_bb | snake.head_bb) & others == 0 {
+                    let to = |mov: &Move| snake.head + mov.direction.into();
+                    let eats = |mov: &Move| self.board.food_bb & u128::from(to(mov)) != 0;
+                    let other = generated_moves.iter().find(|x| !eats(x)).copied();
+                    generated_moves.retain(|x| eats(x));
+                    generated_moves.extend(other);
                 }
+                moves.push(generated_moves);
             }
         }
-        let x = array_vec![[Move; SNAKE_MAX] => predet_move];
+        let x = array_vec![[Move; 4] => predet_move];
         moves.push(x);
-        cartesian_product(moves)
+        JointMoves::new(moves)
+    }
+
+    fn doomed_moves(&self, id: usize) -> ArrayVec<[Move; 4]> {
+        let legal = self.tagged_moves(id, MoveMode::Legal);
+        let mut out: ArrayVec<[Move; 4]> = legal
+            .iter()
+            .filter(|x| x.reason != MoveReason::Wall)
+            .map(|x| x.mov)
+            .collect();
+        if out.is_empty() {
+            if let Some(first) = legal.first() {
+                out.push(first.mov);
+            }
+        }
+        out
     }
 }
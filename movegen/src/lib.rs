@@ -1,19 +1,50 @@
+/// Move generation for all snakes at once on bitboards
+pub mod bitmove;
+/// Lazy Cartesian product of every snake's moves
+pub mod cart_prod;
+
+pub use crate::perft::{divide, perft};
+/// Checks make_move against recorded games
+pub mod conformance;
+/// Game history with undo and replay
+pub mod game;
 /// Generate move functions
 pub mod genmove;
 /// Make and unmake move functions
 pub mod makeunmake;
-
-mod cart_prod;
+/// Joint moves packed into a u16
+pub mod packed;
+/// Checks that unmake_move puts back everything make_move changed
+#[cfg(feature = "paranoid")]
+pub mod paranoid;
+/// Leaf counting for checking move generation
+pub mod perft;
+/// Food spawning chance outcomes
+pub mod spawn;
+/// Which cells heads can meet in next turn and who wins there
+pub mod threat;
+/// Works out what happened between consecutive requests of a game
+pub mod tracker;
 #[cfg(test)]
 mod tests {
     use std::fs;
 
-    use board::board::GameRequest;
+    use board::bitboard::board_mask;
+    use board::board::{Coordinate, GameRequest};
+    use board::maps::GameMap;
+    use board::notation::Notation;
     use board::small::SmallRequest;
     use tinyvec::array_vec;
 
-    use crate::genmove::GenMove;
-    use crate::makeunmake::{MakeUnmake, Move};
+    use crate::bitmove::{mask_moves, BitMoves};
+    use crate::conformance::check_game;
+    use crate::game::Game;
+    use crate::genmove::{GenMove, MoveMode, MoveReason};
+    use crate::makeunmake::{Direction, MakeUnmake, Move};
+    use crate::packed::PackedMoves;
+    use crate::spawn::{SpawnFood, SPAWN_SAMPLES};
+    use crate::threat::Threats;
+    use crate::tracker::Tracker;
     use pretty_assertions::assert_eq;
     #[test]
     fn it_works() {
@@ -21,77 +52,49 @@ mod tests {
         assert_eq!(result, 4);
     }
 
-    #[test]
-    fn perft_food_test() {
-        // get file
+    /// Runs perft on a fixture and checks every make_move was unmade, the paranoid feature checks it at every node
+    fn perft_restores(name: &str) {
         let contents =
-            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "\\tests\\food.json")
+            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/" + name + ".json")
                 .expect("Something went wrong reading the file");
-        // make it into a smallrequest
         let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
         let mut small = seralized.into_small();
-        // run perft depth 4
         let mut t0 = small.clone();
-        perft(&mut small, 5, true, None);
+        assert!(crate::perft(&mut small, 5) > 0);
+        // eaten food comes back, but not always where it was in the list
         t0.board.food.sort();
         small.board.food.sort();
         assert_eq!(t0.board.food, small.board.food);
+        assert_eq!(t0.board.food_bb, small.board.food_bb);
+        assert_eq!(small.board.snakes, t0.board.snakes);
+        assert_eq!(small.board.hazards, t0.board.hazards);
+        assert_eq!(small.turn, t0.turn);
     }
+
+    #[test]
+    fn perft_food_test() {
+        perft_restores("food");
+    }
+
     #[test]
     fn perft_wall_collision_test() {
-        // get file
-        let contents =
-            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "\\tests\\wall.json")
-                .expect("Something went wrong reading the file");
-        // make it into a smallrequest
-        let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
-        let mut small = seralized.into_small();
-        // run perft depth 4
-        let mut t0 = small.clone();
-        perft(&mut small, 5, true, None);
-        t0.board.food.sort();
-        small.board.food.sort();
-        assert_eq!(t0.board.food, small.board.food);
-        assert_eq!(small.board.snakes, t0.board.snakes);
+        perft_restores("wall");
     }
+
     #[test]
     fn perft_body_collision_test() {
-        // get file
-        let contents =
-            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "\\tests\\body.json")
-                .expect("Something went wrong reading the file");
-        // make it into a smallrequest
-        let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
-        let mut small = seralized.into_small();
-        // run perft depth 4
-        let mut t0 = small.clone();
-        perft(&mut small, 5, true, None);
-        t0.board.food.sort();
-        small.board.food.sort();
-        assert_eq!(t0.board.food, small.board.food);
-        assert_eq!(small.board.snakes, t0.board.snakes);
+        perft_restores("body");
     }
+
     #[test]
     fn perft_head_collision_test() {
-        // get file
-        let contents =
-            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "\\tests\\head.json")
-                .expect("Something went wrong reading the file");
-        // make it into a smallrequest
-        let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
-        let mut small = seralized.into_small();
-        // run perft depth 4
-        let mut t0 = small.clone();
-        perft(&mut small, 5, true, None);
-        t0.board.food.sort();
-        small.board.food.sort();
-        assert_eq!(t0.board.food, small.board.food);
-        assert_eq!(small.board.snakes, t0.board.snakes);
+        perft_restores("head");
     }
+
     #[test]
     fn move_generation_food() {
         let contents =
-            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "\\tests\\body.json")
+            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/body.json")
                 .expect("Something went wrong reading the file");
         // make it into a smallrequest
         let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
@@ -113,7 +116,7 @@ mod tests {
 
     fn movegen_same_direction_test() {
         let contents =
-            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "\\tests\\movegen.json")
+            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/movegen.json")
                 .expect("Something went wrong reading the file");
         // make it into a smallrequest
         let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
@@ -125,26 +128,477 @@ mod tests {
         assert_eq!(moves_you, moves_actual)
     }
 
-    fn perft(state: &mut SmallRequest, depth: u8, player: bool, you_move: Option<Move>) {
-        if state.amount_alive() as usize == 0
-            || !state.board.snakes[state.you].alive
-            || state.amount_alive() as usize == 1
-            || depth == 0
+    #[test]
+    fn spawn_outcomes_test() {
+        let contents =
+            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/food.json")
+                .expect("Something went wrong reading the file");
+        // make it into a smallrequest
+        let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
+        let mut small = seralized.into_small();
+        let t0 = small.clone();
+        // enough food on the board, so its the 14% roll split over the samples
+        let outcomes = small.spawn_outcomes();
+        assert_eq!(outcomes.len(), SPAWN_SAMPLES + 1);
+        let total: u32 = outcomes.iter().map(|x| x.weight).sum();
+        assert_eq!(total, 100 * SPAWN_SAMPLES as u32);
+        for outcome in &outcomes[..SPAWN_SAMPLES] {
+            assert_eq!(outcome.weight, 14);
+            assert_eq!(outcome.food.len(), 1);
+            assert_eq!(small.board.food_bb & u128::from(outcome.food[0]), 0);
+            small.spawn_food(&outcome.food);
+            small.unspawn_food(&outcome.food);
+            assert_eq!(small.board.food, t0.board.food);
+            assert_eq!(small.board.food_bb, t0.board.food_bb);
+        }
+        // below the minimum food always spawns
+        small.board.food.clear();
+        small.board.food_bb = 0;
+        let outcomes = small.spawn_outcomes();
+        assert!(outcomes.iter().all(|x| x.food.len() == 1));
+    }
+
+    #[test]
+    fn map_hazards_test() {
+        let contents =
+            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/movegen.json")
+                .expect("Something went wrong reading the file");
+        // make it into a smallrequest
+        let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
+        let mut small = seralized.into_small();
+
+        // arcade maze hazards are walls
+        let left = small.board.snakes[0].head + Coordinate { x: -1, y: 0 };
+        small.rules.map = GameMap::ArcadeMaze;
+        small.board.hazards.push(left);
+        small.board.hazard_bb |= u128::from(left);
+        let mut moves_you = small.snake_moves(0);
+        moves_you.sort();
+        let mut moves_actual = array_vec![[Move;4] => Move::new(crate::makeunmake::Direction::Down, 0) , Move::new(crate::makeunmake::Direction::Right, 0)];
+        moves_actual.sort();
+        assert_eq!(moves_you, moves_actual);
+
+        // the spiral keeps going the way the first hazards went, one every 3 turns
+        let center = Coordinate { x: 5, y: 5 };
+        let spiral: Vec<Coordinate> = [(0, 0), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0)]
+            .iter()
+            .map(|(x, y)| center + Coordinate { x: *x, y: *y })
+            .collect();
+        let predicted = GameMap::Spiral.predict_hazards(&spiral[..3], 7, 16, 11, 11);
+        assert_eq!(predicted, spiral);
+
+        // and the search sees it happen
+        small.rules.map = GameMap::Spiral;
+        small.board.hazards = spiral[..3].to_vec();
+        small.board.hazard_bb = 0;
+        small.turn = 9;
+        let t0 = small.clone();
+        let delta = small.make_move(&small.all_snake_moves(moves_you[0])[0]);
+        assert_eq!(small.board.hazards, spiral[..4].to_vec());
+        small.unmake_move(&delta);
+        assert_eq!(small.board.hazards, t0.board.hazards);
+        assert_eq!(small.board.hazard_bb, t0.board.hazard_bb);
+        assert_eq!(small.turn, t0.turn);
+    }
+
+    #[test]
+    fn conformance_logs_test() {
+        // the synthetic_ logs come from our own simulation, so they only catch regressions and not misreadings of the rules,
+        // matching the official engine needs games recorded from it, which can be dropped in next to them as they are
+        for entry in fs::read_dir(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/logs")
+            .expect("Something went wrong reading the logs")
+        {
+            let path = entry.unwrap().path();
+            let contents =
+                fs::read_to_string(&path).expect("Something went wrong reading the file");
+            let states: Vec<GameRequest> = contents
+                .lines()
+                .map(|line| serde_json::from_str(line).expect("Invalid json"))
+                .collect();
+            let mismatches = check_game(&states);
+            for mismatch in &mismatches {
+                println!("{}: {}", path.display(), mismatch);
+            }
+            assert!(mismatches.is_empty());
+        }
+    }
+
+    #[test]
+    fn shared_food_test() {
+        // 0 and 2 meet on one food while 1 eats another, the shared one must only be taken once
+        let mut small = SmallRequest::from_notation(
+            "11x11;f 5,5 1,5;y 100 5,4 5,3 5,2;s 100 1,4 1,3 1,2;s 100 5,6 5,7 5,8",
+        )
+        .unwrap();
+        let t0 = small.clone();
+        let delta = small.make_move(&array_vec![[Move; 4] =>
+            Move::new(Direction::Up, 0),
+            Move::new(Direction::Up, 1),
+            Move::new(Direction::Down, 2),
+        ]);
+        assert!(small.board.food.is_empty());
+        assert_eq!(small.board.food_bb, 0);
+        assert!(!small.board.snakes[0].alive);
+        assert!(small.board.snakes[1].alive);
+        assert!(!small.board.snakes[2].alive);
+        assert_eq!(small.board.snakes[1].length, 4);
+        small.unmake_move(&delta);
+        small.board.food.sort();
+        let mut food = t0.board.food.clone();
+        food.sort();
+        assert_eq!(small.board.food, food);
+        assert_eq!(small.board.food_bb, t0.board.food_bb);
+        assert_eq!(small.board.snakes, t0.board.snakes);
+    }
+
+    #[test]
+    fn tracker_test() {
+        let contents = fs::read_to_string(
+            env!("CARGO_MANIFEST_DIR").to_string() + "/tests/logs/synthetic_duel_head.jsonl",
+        )
+        .expect("Something went wrong reading the file");
+        let states: Vec<GameRequest> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("Invalid json"))
+            .collect();
+        let mut tracker = Tracker::new();
+        assert_eq!(tracker.observe(&states[0]), None);
+        let mut died = 0;
+        for pair in states.windows(2) {
+            let changes = tracker.observe(&pair[1]).expect("consecutive turns");
+            // playing the moves we worked out gives the next request back
+            let mut small = pair[0].into_small();
+            let ids = pair[0].snake_ids();
+            let mut moves = array_vec![];
+            for change in &changes.moves {
+                let id = ids.iter().position(|x| *x == change.id).unwrap();
+                moves.push(Move::new(change.direction.unwrap(), id as u8));
+                let head = small.board.snakes[id].head + change.direction.unwrap().into();
+                assert_eq!(change.ate, changes.eaten_food.contains(&head));
+            }
+            small.make_move(&moves);
+            let next = pair[1].into_small();
+            for (next_id, name) in pair[1].snake_ids().iter().enumerate() {
+                let id = ids.iter().position(|x| x == name).unwrap();
+                assert_eq!(small.board.snakes[id].body, next.board.snakes[next_id].body);
+            }
+            died += changes.died.len();
+        }
+        assert_eq!(died, 2);
+        // a missed turn can't be worked out, it starts over from there
+        let mut tracker = Tracker::new();
+        tracker.observe(&states[0]);
+        assert_eq!(tracker.observe(&states[2]), None);
+        assert!(tracker.observe(&states[3]).is_some());
+    }
+
+    #[test]
+    fn threat_map_test() {
+        let contents =
+            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/head.json")
+                .expect("Something went wrong reading the file");
+        let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
+        let small = seralized.into_small();
+        let threats = small.threat_map();
+        // both heads can go to 3,5 and you are the longer one
+        let cell = Coordinate { x: 3, y: 5 };
+        assert_eq!(threats.contested, u128::from(cell));
+        assert_eq!(threats.snakes_at(cell), 0b11);
+        assert_eq!(threats.winner_at(cell), Some(0));
+        assert_eq!(threats.kills(0), u128::from(cell));
+        assert_eq!(threats.danger(0), 0);
+        assert_eq!(threats.danger(1), u128::from(cell));
+        assert_eq!(threats.reach[0].count_ones(), 3);
+        assert_eq!(threats.winner_at(Coordinate { x: 9, y: 9 }), None);
+
+        let small =
+            SmallRequest::from_notation("11x11;y 100 5,5 5,4 5,3;s 100 5,7 5,8 5,9").unwrap();
+        assert_eq!(
+            small.threat_map().winner_at(Coordinate { x: 5, y: 6 }),
+            None
+        );
+    }
+
+    #[test]
+    fn tagged_moves_test() {
+        let contents =
+            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/head.json")
+                .expect("Something went wrong reading the file");
+        // make it into a smallrequest
+        let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
+        let small = seralized.into_small();
+        // the shorter snake risks a head to head by going up, you don't
+        let risky = small.tagged_moves(1, MoveMode::Risky);
+        assert_eq!(risky.len(), 1);
+        assert_eq!(risky[0].mov, Move::new(crate::makeunmake::Direction::Up, 1));
+        assert_eq!(risky[0].reason, MoveReason::HeadToHead(0));
+        assert_eq!(small.tagged_moves(1, MoveMode::Safe).len(), 2);
+        assert_eq!(small.tagged_moves(0, MoveMode::Safe).len(), 3);
+
+        let contents =
+            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/wall.json")
+                .expect("Something went wrong reading the file");
+        let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
+        let small = seralized.into_small();
+        // legal moves keep the one into the wall, tagged, and the tail is out of the way by then
+        let mut legal = small.tagged_moves(0, MoveMode::Legal);
+        legal.sort();
+        let reasons: Vec<MoveReason> = legal.iter().map(|x| x.reason).collect();
+        assert_eq!(
+            reasons,
+            vec![MoveReason::Clear, MoveReason::Clear, MoveReason::Wall]
+        );
+    }
+
+    #[test]
+    fn move_masks_test() {
+        let mut states = vec![];
+        for name in ["body", "food", "four_player", "head", "movegen", "wall"] {
+            let contents = fs::read_to_string(
+                env!("CARGO_MANIFEST_DIR").to_string() + "/tests/" + name + ".json",
+            )
+            .expect("Something went wrong reading the file");
+            states.push(serde_json::from_str::<GameRequest>(&contents).expect("Invalid json"));
+        }
+        for entry in fs::read_dir(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/logs")
+            .expect("Something went wrong reading the logs")
         {
-            return;
+            let contents = fs::read_to_string(entry.unwrap().path())
+                .expect("Something went wrong reading the file");
+            for line in contents.lines() {
+                states.push(serde_json::from_str(line).expect("Invalid json"));
+            }
         }
-        if player {
-            for x in state.snake_moves(state.you) {
-                perft(state, depth, !player, Some(x));
+        for state in states {
+            let small = state.into_small();
+            let masks = small.move_masks();
+            for snake in &small.board.snakes {
+                assert_eq!(
+                    mask_moves(masks[snake.id as usize], snake.id),
+                    small.snake_moves(snake.id as usize)
+                );
             }
-        } else {
-            for x in state.all_snake_moves(you_move.unwrap()) {
-                let t0 = state.clone();
-                let delta = state.make_move(&x);
-                perft(state, depth - 1, !player, None);
-                state.unmake_move(&delta);
-                assert_eq!(state.board.snakes, t0.board.snakes);
+        }
+    }
+
+    #[test]
+    fn board_mask_test() {
+        for width in 0..=11u8 {
+            for height in 0..=11u8 {
+                let mut expected = 0u128;
+                for y in 0..height as i32 {
+                    for x in 0..width as i32 {
+                        expected |= u128::from(Coordinate { x, y });
+                    }
+                }
+                assert_eq!(board_mask(width, height), expected, "{}x{}", width, height);
+            }
+        }
+    }
+
+    #[test]
+    fn joint_moves_test() {
+        let contents =
+            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/four_player.json")
+                .expect("Something went wrong reading the file");
+        // make it into a smallrequest
+        let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
+        let small = seralized.into_small();
+        let you_move = Move::new(crate::makeunmake::Direction::Down, 0);
+        let joint_moves = small.joint_moves(you_move);
+        let total = joint_moves.total();
+        let all: Vec<_> = joint_moves.collect();
+        assert_eq!(all.len(), total);
+        let mut deduped = all.clone();
+        deduped.sort();
+        deduped.dedup();
+        assert_eq!(deduped.len(), total);
+        assert!(all.iter().all(|x| x.contains(&you_move)));
+
+        // ordering puts every snake's smallest move into the first joint move
+        let first = small
+            .joint_moves(you_move)
+            .ordered_by(|x| x.direction)
+            .next()
+            .unwrap();
+        for mov in first {
+            assert!(small
+                .snake_moves(mov.id as usize)
+                .iter()
+                .all(|x| x.direction >= mov.direction || mov.id == 0));
+        }
+    }
+
+    #[test]
+    fn joint_moves_near_test() {
+        let contents =
+            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/four_player.json")
+                .expect("Something went wrong reading the file");
+        // make it into a smallrequest
+        let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
+        let small = seralized.into_small();
+        let you_move = Move::new(crate::makeunmake::Direction::Down, 0);
+        // every opponent is more than 2 from every other head and can't eat, so each keeps one move
+        assert_eq!(small.joint_moves_near(you_move, 1).total(), 1);
+        // snakes 1 and 3 have their heads 4 apart, snake 2 is still more than 4 from every head
+        assert_eq!(
+            small.joint_moves_near(you_move, 2).total(),
+            small.snake_moves(1).len() * small.snake_moves(3).len()
+        );
+        assert_eq!(
+            small.joint_moves_near(you_move, 3).total(),
+            small.joint_moves(you_move).total()
+        );
+
+        // far from you, but close enough to kill snake 1 head to head, and that frees the cells next to your head
+        let small = SmallRequest::from_notation(
+            "11x11;y 100 5,0 4,0 3,0;s 100 8,2 7,2 6,2 5,2 4,2 3,2;s 100 10,2 10,3 9,3 8,3 7,3 6,3 5,3",
+        )
+        .unwrap();
+        assert_eq!(
+            small
+                .joint_moves_near(Move::new(Direction::Up, 0), 1)
+                .total(),
+            small.joint_moves(Move::new(Direction::Up, 0)).total()
+        );
+
+        // a far away snake keeps its move onto food along with one of the others
+        let small =
+            SmallRequest::from_notation("11x11;f 10,1;y 100 0,10 0,9 0,8;s 100 9,1 9,0 8,0")
+                .unwrap();
+        let mut kept: Vec<_> = small
+            .joint_moves_near(Move::new(Direction::Down, 0), 3)
+            .map(|x| x.iter().find(|mov| mov.id == 1).unwrap().direction)
+            .collect();
+        kept.sort();
+        let mut expected = vec![Direction::Up, Direction::Right];
+        expected.sort();
+        assert_eq!(kept, expected);
+    }
+
+    #[test]
+    #[cfg(feature = "paranoid")]
+    #[should_panic(expected = "snake 0 health: was 100, now 51")]
+    fn paranoid_unmake_test() {
+        let mut small =
+            SmallRequest::from_notation("11x11;f 5,5;y 100 1,1 1,2 1,3;s 90 9,9 9,8 9,7").unwrap();
+        let mut moves = array_vec![];
+        moves.push(Move::new(Direction::Up, 0));
+        moves.push(Move::new(Direction::Up, 1));
+        let delta = small.make_move(&moves);
+        small.board.snakes[0].health = 50;
+        small.unmake_move(&delta);
+    }
+
+    #[test]
+    fn doomed_moves_test() {
+        // boxed into the corner by its own body, the only move that stays on the board is into it
+        let small =
+            SmallRequest::from_notation("11x11;y 100 5,5 5,4 5,3;s 100 0,10 1,10 1,9 0,9 0,8")
+                .unwrap();
+        assert!(small.snake_moves(1).is_empty());
+        let doomed = small.doomed_moves(1);
+        assert_eq!(doomed.len(), 1);
+        assert_eq!(
+            small.board.snakes[1].head + doomed[0].direction.into(),
+            Coordinate { x: 0, y: 9 }
+        );
+        for joint_move in small.joint_moves(small.snake_moves(0)[0]) {
+            assert!(joint_move.contains(&doomed[0]));
+        }
+    }
+
+    #[test]
+    fn packed_moves_test() {
+        let contents =
+            fs::read_to_string(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/four_player.json")
+                .expect("Something went wrong reading the file");
+        let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
+        let small = seralized.into_small();
+        let mut seen = vec![];
+        for you_move in small.snake_moves(small.you) {
+            for joint_move in small.joint_moves(you_move) {
+                let packed = PackedMoves::from(&joint_move);
+                let mut sorted = joint_move;
+                sorted.sort_unstable_by_key(|x| x.id);
+                assert_eq!(packed.unpack(), sorted);
+                assert_eq!(PackedMoves::from_bits(packed.bits()), packed);
+                seen.push(packed.bits());
             }
         }
+        let total = seen.len();
+        seen.sort_unstable();
+        seen.dedup();
+        assert_eq!(seen.len(), total);
+    }
+
+    #[test]
+    fn game_history_test() {
+        let root =
+            SmallRequest::from_notation("11x11;f 5,5;y 100 1,1 1,2 1,3;s 90 9,9 9,8 9,7").unwrap();
+        let mut game = Game::new(root.clone());
+        for _ in 0..6 {
+            let you_move = game.state().snake_moves(game.state().you)[0];
+            let joint_move = game.state().joint_moves(you_move).next().unwrap();
+            game.push(joint_move);
+        }
+        assert_eq!(game.ply(), 6);
+        let replayed = Game::replay(root.clone(), game.history());
+        assert_eq!(replayed.state().board.snakes, game.state().board.snakes);
+        assert_eq!(game.state_at(6).board.snakes, game.state().board.snakes);
+
+        let last = game.pop().unwrap();
+        assert_eq!(last, replayed.history()[5]);
+        assert_eq!(game.state().board.snakes, game.state_at(5).board.snakes);
+        while game.pop().is_some() {}
+        assert_eq!(game.ply(), 0);
+        assert_eq!(game.state().turn, root.turn);
+        assert_eq!(game.state().board.snakes, root.board.snakes);
+    }
+
+    #[test]
+    fn perft_counts_test() {
+        // known node counts, if movegen changes these it should be on purpose
+        for (name, nodes) in [
+            ("body", 77),
+            ("food", 841),
+            ("head", 426),
+            ("movegen", 378),
+            ("wall", 648),
+        ] {
+            let contents = fs::read_to_string(
+                env!("CARGO_MANIFEST_DIR").to_string() + "/tests/" + name + ".json",
+            )
+            .expect("Something went wrong reading the file");
+            let seralized: GameRequest = serde_json::from_str(&contents).expect("Invalid json");
+            let mut small = seralized.into_small();
+            assert_eq!(crate::perft(&mut small, 3), nodes, "{}", name);
+            let divided: u64 = crate::divide(&mut small, 3).iter().map(|x| x.nodes).sum();
+            assert_eq!(divided, nodes, "{}", name);
+        }
+        let mut small =
+            SmallRequest::from_notation("11x11;f 5,5;y 100 1,1 1,2 1,3;s 90 9,9 9,8 9,7").unwrap();
+        assert_eq!(crate::perft(&mut small, 4), 1225);
+        assert_eq!(
+            SmallRequest::from_notation(&small.to_notation())
+                .unwrap()
+                .board
+                .snakes,
+            small.board.snakes
+        );
+    }
+
+    #[test]
+    fn notation_errors_test() {
+        assert!(SmallRequest::from_notation(
+            "11x11;y 100 1,1;s 100 3,3;s 100 5,5;s 100 7,7;s 100 9,9"
+        )
+        .is_err());
+        assert!(
+            SmallRequest::from_notation("11x11;y 100 1,1;s 100 3,3;s 100 5,5;s 100 7,7").is_ok()
+        );
+        assert!(SmallRequest::from_notation("7x7;y 100 7,1 6,1").is_err());
+        assert!(SmallRequest::from_notation("7x7;f 3,-1;y 100 1,1").is_err());
+        assert!(SmallRequest::from_notation("7x7;h 0,9;y 100 1,1").is_err());
     }
 }
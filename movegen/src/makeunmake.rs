@@ -14,6 +14,13 @@ pub struct Delta {
     prev_health: ArrayVec<[(u8, u8); SNAKE_MAX]>,
     /// Tails of snakes that were there before, (id, position)
     tails: ArrayVec<[(u8, Coordinate); SNAKE_MAX]>,
+    /// Hazard that the map added this turn
+    hazard_spawn: Option<Coordinate>,
+    /// Health taken by hazards, (id, damage)
+    hazard_damage: ArrayVec<[(u8, u8); SNAKE_MAX]>,
+    /// The whole state from before the move, checked on unmake
+    #[cfg(feature = "paranoid")]
+    snapshot: crate::paranoid::Snapshot,
 }
 /// Make and unmake move trait
 pub trait MakeUnmake {
@@ -25,8 +32,10 @@ pub trait MakeUnmake {
 trait Helpers {
     fn move_snakes(&mut self, moves: &ArrayVec<[Move; SNAKE_MAX]>, delta: &mut Delta);
     fn reduce_health(&mut self);
+    fn damage_hazards(&mut self, delta: &mut Delta);
     fn maybe_feed_snakes(&mut self, delta: &mut Delta);
     fn maybe_eliminiate_snakes(&mut self, delta: &mut Delta);
+    fn maybe_spawn_hazards(&mut self, delta: &mut Delta);
 }
 /// Stores a move for a given id
 
@@ -45,26 +54,21 @@ impl Move {
 }
 
 /// Directions that the snakes can move
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
 
 pub enum Direction {
+    #[default]
     Up,
     Down,
     Left,
     Right,
 }
 
-impl Default for Direction {
-    fn default() -> Self {
-        Self::Up
-    }
-}
-
 impl Helpers for SmallRequest {
     fn move_snakes(&mut self, moves: &ArrayVec<[Move; SNAKE_MAX]>, delta: &mut Delta) {
         for snake_move in moves {
             // intermediate snake storage to prevent code duplication
-            let mut snake = &mut self.board.snakes[snake_move.id as usize];
+            let snake = &mut self.board.snakes[snake_move.id as usize];
 
             // move the snakes head
             snake.head += Coordinate::from(snake_move.direction);
@@ -82,7 +86,7 @@ impl Helpers for SmallRequest {
             snake.head_bb |= u128::from(snake.head);
 
             // remove the old tail
-            snake.body_bb &= !(u128::from(snake.body[snake.length as usize - 1]));
+            snake.body_bb &= !(u128::from(snake.body[snake.length as usize]));
 
             // add in the new tail
             snake.body_bb |= u128::from(snake.body[snake.length as usize - 1]);
@@ -106,25 +110,53 @@ impl Helpers for SmallRequest {
         }
     }
 
+    fn damage_hazards(&mut self, delta: &mut Delta) {
+        if self.board.hazard_bb == 0 {
+            return;
+        }
+        for snake in &mut self.board.snakes {
+            // food cancels out the hazard
+            if !snake.alive
+                || snake.head_bb & self.board.hazard_bb == 0
+                || snake.head_bb & self.board.food_bb != 0
+            {
+                continue;
+            }
+            // stacked hazards each do damage
+            let stacks = self
+                .board
+                .hazards
+                .iter()
+                .filter(|x| **x == snake.head)
+                .count();
+            let damage = (self.rules.hazard_damage as usize * stacks).min(snake.health as usize);
+            snake.health -= damage as u8;
+            delta.hazard_damage.push((snake.id, damage as u8));
+        }
+    }
+
     fn maybe_feed_snakes(&mut self, delta: &mut Delta) {
+        let mut eaten = 0;
         for snake in &mut self.board.snakes {
-            if (snake.head_bb & self.board.food_bb) != 0 {
+            if snake.alive && (snake.head_bb & self.board.food_bb) != 0 {
                 delta.prev_health.push((snake.id, snake.health));
                 snake.body.push(*snake.body.last().unwrap());
                 snake.health = 100;
                 snake.length += 1;
-                delta
-                    .eaten_food
-                    .push(Coordinate::from(snake.head_bb & self.board.food_bb));
+                eaten |= snake.head_bb;
             }
         }
 
-        delta.eaten_food.dedup();
-        for food in &delta.eaten_food {
+        // food that several snakes met on is only there once, so go by the bitboard
+        while eaten != 0 {
+            let food = eaten & eaten.wrapping_neg();
+            eaten &= eaten - 1;
+            let coord = Coordinate::from(food);
             self.board
                 .food
-                .swap_remove(self.board.food.iter().position(|x| *x == *food).expect(""));
-            self.board.food_bb &= !u128::from(*food);
+                .swap_remove(self.board.food.iter().position(|x| *x == coord).expect(""));
+            self.board.food_bb &= !food;
+            delta.eaten_food.push(coord);
         }
     }
 
@@ -203,6 +235,20 @@ impl Helpers for SmallRequest {
         }
         delta.died.dedup();
     }
+
+    fn maybe_spawn_hazards(&mut self, delta: &mut Delta) {
+        let spawn = self.rules.map.hazard_spawn(
+            &self.board.hazards,
+            self.turn,
+            self.board.width,
+            self.board.height,
+        );
+        if let Some(hazard) = spawn {
+            self.board.hazards.push(hazard);
+            self.board.hazard_bb |= u128::from(hazard);
+            delta.hazard_spawn = Some(hazard);
+        }
+    }
 }
 impl MakeUnmake for SmallRequest {
     fn make_move(&mut self, moves: &ArrayVec<[Move; SNAKE_MAX]>) -> Delta {
@@ -211,18 +257,37 @@ impl MakeUnmake for SmallRequest {
             died: array_vec![],
             prev_health: array_vec![],
             tails: array_vec![],
+            hazard_spawn: None,
+            hazard_damage: array_vec![],
+            #[cfg(feature = "paranoid")]
+            snapshot: crate::paranoid::Snapshot {
+                state: self.clone(),
+                moves: *moves,
+            },
         };
+        self.turn += 1;
+
         self.move_snakes(moves, &mut out);
 
         self.reduce_health();
 
+        self.damage_hazards(&mut out);
+
         self.maybe_feed_snakes(&mut out);
 
         self.maybe_eliminiate_snakes(&mut out);
 
+        self.maybe_spawn_hazards(&mut out);
+
         out
     }
     fn unmake_move(&mut self, delta: &Delta) {
+        self.turn -= 1;
+        // take away the hazard the map added
+        if let Some(hazard) = delta.hazard_spawn {
+            self.board.hazards.pop();
+            self.board.hazard_bb &= !u128::from(hazard);
+        }
         // put food back
         for food in &delta.eaten_food {
             self.board.food.push(*food);
@@ -238,6 +303,10 @@ impl MakeUnmake for SmallRequest {
             self.board.snakes[*id as usize].body.pop().unwrap();
             self.board.snakes[*id as usize].length -= 1;
         }
+        // undo hazard damage
+        for (id, damage) in &delta.hazard_damage {
+            self.board.snakes[*id as usize].health += *damage;
+        }
         // increase health
         for snake in &mut self.board.snakes {
             if snake.alive {
@@ -261,6 +330,8 @@ impl MakeUnmake for SmallRequest {
                 snake.body_bb |= u128::from(snake.body[snake.length as usize - 1]);
             }
         }
+        #[cfg(feature = "paranoid")]
+        crate::paranoid::verify(&delta.snapshot, self);
     }
 }
 
@@ -275,6 +346,24 @@ impl From<Direction> for Coordinate {
     }
 }
 impl Direction {
+    /// Number from 0 to 3, used to pack moves
+    pub fn index(&self) -> u8 {
+        match self {
+            Direction::Up => 0,
+            Direction::Down => 1,
+            Direction::Left => 2,
+            Direction::Right => 3,
+        }
+    }
+    /// Direction back from its index, only the low 2 bits are looked at
+    pub fn from_index(index: u8) -> Self {
+        match index & 3 {
+            0 => Direction::Up,
+            1 => Direction::Down,
+            2 => Direction::Left,
+            _ => Direction::Right,
+        }
+    }
     pub fn to_string(&self) -> &str {
         match self {
             Direction::Up => "up",
@@ -0,0 +1,87 @@
+use board::small::SNAKE_MAX;
+use tinyvec::{array_vec, ArrayVec};
+
+use crate::makeunmake::{Direction, Move};
+
+// 2 direction bits and 1 present bit per snake have to fit in the u16
+const _: () = assert!(SNAKE_MAX <= 5);
+
+/// Bit where the mask of snakes that have a move starts
+const PRESENT_SHIFT: usize = 2 * SNAKE_MAX;
+
+/// A joint move packed into a u16, cheap to copy, compare and hash.
+/// The low bits hold 2 bits of direction per snake id, above them is a mask of which snakes have a move.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PackedMoves(u16);
+
+impl PackedMoves {
+    /// No moves at all
+    pub fn new() -> Self {
+        Self(0)
+    }
+
+    /// Rebuild from bits given out by bits()
+    pub fn from_bits(bits: u16) -> Self {
+        Self(bits)
+    }
+
+    /// The raw bits, to use as a table key
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+
+    /// Whether no snake has a move
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Add a move, replacing the one the snake already had
+    pub fn set(&mut self, mov: Move) {
+        let id = mov.id as usize;
+        self.0 &= !(0b11 << (2 * id));
+        self.0 |= (mov.direction.index() as u16) << (2 * id);
+        self.0 |= 1 << (PRESENT_SHIFT + id);
+    }
+
+    /// The move of the snake with this id, if it has one
+    pub fn get(&self, id: u8) -> Option<Move> {
+        if self.0 & (1 << (PRESENT_SHIFT + id as usize)) == 0 {
+            return None;
+        }
+        let direction = Direction::from_index((self.0 >> (2 * id as usize)) as u8 & 0b11);
+        Some(Move::new(direction, id))
+    }
+
+    /// Unpack back into a joint move, in order of snake id
+    pub fn unpack(&self) -> ArrayVec<[Move; SNAKE_MAX]> {
+        let mut out = array_vec![];
+        for id in 0..SNAKE_MAX as u8 {
+            if let Some(mov) = self.get(id) {
+                out.push(mov);
+            }
+        }
+        out
+    }
+}
+
+impl From<&[Move]> for PackedMoves {
+    fn from(moves: &[Move]) -> Self {
+        let mut out = Self::new();
+        for mov in moves {
+            out.set(*mov);
+        }
+        out
+    }
+}
+
+impl From<&ArrayVec<[Move; SNAKE_MAX]>> for PackedMoves {
+    fn from(moves: &ArrayVec<[Move; SNAKE_MAX]>) -> Self {
+        Self::from(moves.as_slice())
+    }
+}
+
+impl From<PackedMoves> for ArrayVec<[Move; SNAKE_MAX]> {
+    fn from(moves: PackedMoves) -> Self {
+        moves.unpack()
+    }
+}
@@ -0,0 +1,105 @@
+use board::small::{SmallRequest, SNAKE_MAX};
+use tinyvec::ArrayVec;
+
+use crate::makeunmake::Move;
+
+/// Copy of the state from before a make_move, kept in the delta so unmake_move can check its work
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    /// The state before the move
+    pub state: SmallRequest,
+    /// The joint move that was made
+    pub moves: ArrayVec<[Move; SNAKE_MAX]>,
+}
+
+/// Every way the restored state differs from the snapshot, one line each.
+/// Food order isn't part of the position, eating swaps it around, so food is compared as a set.
+pub fn diff(before: &SmallRequest, after: &SmallRequest) -> Vec<String> {
+    let mut out = vec![];
+    let mut field = |name: &str, a: String, b: String| {
+        if a != b {
+            out.push(format!("{}: was {}, now {}", name, a, b));
+        }
+    };
+    field("turn", before.turn.to_string(), after.turn.to_string());
+    field("you", before.you.to_string(), after.you.to_string());
+    field(
+        "rules",
+        format!("{:?}", before.rules),
+        format!("{:?}", after.rules),
+    );
+    field(
+        "food_bb",
+        format!("{:#x}", before.board.food_bb),
+        format!("{:#x}", after.board.food_bb),
+    );
+    field(
+        "hazard_bb",
+        format!("{:#x}", before.board.hazard_bb),
+        format!("{:#x}", after.board.hazard_bb),
+    );
+    field(
+        "zobrist",
+        before.board.zobrist.to_string(),
+        after.board.zobrist.to_string(),
+    );
+    let mut food_before = before.board.food.clone();
+    let mut food_after = after.board.food.clone();
+    food_before.sort();
+    food_after.sort();
+    field(
+        "food",
+        format!("{:?}", food_before),
+        format!("{:?}", food_after),
+    );
+    field(
+        "hazards",
+        format!("{:?}", before.board.hazards),
+        format!("{:?}", after.board.hazards),
+    );
+    field(
+        "snake count",
+        before.board.snakes.len().to_string(),
+        after.board.snakes.len().to_string(),
+    );
+    for (a, b) in before.board.snakes.iter().zip(&after.board.snakes) {
+        let name = |x: &str| format!("snake {} {}", a.id, x);
+        field(&name("id"), a.id.to_string(), b.id.to_string());
+        field(&name("alive"), a.alive.to_string(), b.alive.to_string());
+        field(&name("health"), a.health.to_string(), b.health.to_string());
+        field(&name("length"), a.length.to_string(), b.length.to_string());
+        field(
+            &name("head"),
+            format!("{:?}", a.head),
+            format!("{:?}", b.head),
+        );
+        field(
+            &name("body"),
+            format!("{:?}", a.body),
+            format!("{:?}", b.body),
+        );
+        field(
+            &name("head_bb"),
+            format!("{:#x}", a.head_bb),
+            format!("{:#x}", b.head_bb),
+        );
+        field(
+            &name("body_bb"),
+            format!("{:#x}", a.body_bb),
+            format!("{:#x}", b.body_bb),
+        );
+    }
+    out
+}
+
+/// Panic if unmake_move didn't bring back the state from the snapshot
+pub fn verify(snapshot: &Snapshot, after: &SmallRequest) {
+    let differences = diff(&snapshot.state, after);
+    if !differences.is_empty() {
+        panic!(
+            "unmake_move didn't restore the state after {:?}\n{}",
+            snapshot.moves,
+            differences.join("\n")
+        );
+    }
+}
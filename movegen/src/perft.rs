@@ -0,0 +1,70 @@
+use board::{
+    rules::GameMode,
+    small::{SmallRequest, SNAKE_MAX},
+};
+use tinyvec::ArrayVec;
+
+use crate::{
+    genmove::GenMove,
+    makeunmake::{MakeUnmake, Move},
+};
+
+/// Leaf count below one joint move from the root
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DivideEntry {
+    /// Your move
+    pub you_move: Move,
+    /// Every snake's move, including yours
+    pub joint_move: ArrayVec<[Move; SNAKE_MAX]>,
+    /// Leaves below it
+    pub nodes: u64,
+}
+
+/// Whether the game has ended, so the tree stops here
+fn game_over(state: &SmallRequest) -> bool {
+    let alive = state.amount_alive();
+    !state.board.snakes[state.you].alive
+        || alive == 0
+        || (alive == 1 && state.rules.mode != GameMode::Solo)
+}
+
+/// Count the positions exactly depth turns ahead, a turn being your move and then every joint move that goes with it.
+/// Lines where the game ends sooner don't count, just like checkmates in chess perft.
+pub fn perft(state: &mut SmallRequest, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    if game_over(state) {
+        return 0;
+    }
+    let mut nodes = 0;
+    for you_move in state.snake_moves(state.you) {
+        for joint_move in state.joint_moves(you_move) {
+            let delta = state.make_move(&joint_move);
+            nodes += perft(state, depth - 1);
+            state.unmake_move(&delta);
+        }
+    }
+    nodes
+}
+
+/// Perft split up by the joint move made from the root, in the order they are generated
+pub fn divide(state: &mut SmallRequest, depth: u8) -> Vec<DivideEntry> {
+    let mut out = vec![];
+    if depth == 0 || game_over(state) {
+        return out;
+    }
+    for you_move in state.snake_moves(state.you) {
+        for joint_move in state.joint_moves(you_move) {
+            let delta = state.make_move(&joint_move);
+            let nodes = perft(state, depth - 1);
+            state.unmake_move(&delta);
+            out.push(DivideEntry {
+                you_move,
+                joint_move,
+                nodes,
+            });
+        }
+    }
+    out
+}
@@ -0,0 +1,143 @@
+use board::{bitboard::board_mask, board::Coordinate, small::SmallRequest};
+use tinyvec::{array_vec, ArrayVec};
+
+/// Most food that can appear in one turn that we model
+pub const SPAWN_MAX: usize = 4;
+
+/// Amount of spawn locations tried when there are too many empty cells to try them all
+pub const SPAWN_SAMPLES: usize = 3;
+
+/// One way food could spawn at the end of a turn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SpawnOutcome {
+    /// Food that appears, empty if nothing spawns
+    pub food: ArrayVec<[Coordinate; SPAWN_MAX]>,
+    /// Relative chance of this outcome compared to the others it was generated with
+    pub weight: u32,
+}
+
+/// Models the standard food spawning rules
+pub trait SpawnFood {
+    /// Every way food could spawn after the current turn.
+    /// Exact when there are at most SPAWN_SAMPLES empty cells, sampled otherwise.
+    fn spawn_outcomes(&self) -> ArrayVec<[SpawnOutcome; SPAWN_SAMPLES + 1]>;
+    /// Put food onto the board
+    fn spawn_food(&mut self, food: &[Coordinate]);
+    /// Take back food that was put down by spawn_food
+    fn unspawn_food(&mut self, food: &[Coordinate]);
+}
+
+/// Cells that food is allowed to spawn in
+fn empty_cells(state: &SmallRequest) -> u128 {
+    let mut occupied = state.board.food_bb;
+    for snake in &state.board.snakes {
+        if snake.alive {
+            occupied |= snake.body_bb | snake.head_bb;
+        }
+    }
+    for hazard in &state.board.hazards {
+        occupied |= u128::from(*hazard);
+    }
+    board_mask(state.board.width, state.board.height) & !occupied
+}
+
+/// The nth set bit on its own
+fn nth_bit(mut bb: u128, n: u32) -> u128 {
+    for _ in 0..n {
+        bb &= bb - 1;
+    }
+    bb & bb.wrapping_neg()
+}
+
+/// Small xorshift so the samples are the same every time the position is seen
+fn next_random(seed: &mut u64) -> u64 {
+    *seed ^= *seed << 13;
+    *seed ^= *seed >> 7;
+    *seed ^= *seed << 17;
+    *seed
+}
+
+impl SpawnFood for SmallRequest {
+    fn spawn_outcomes(&self) -> ArrayVec<[SpawnOutcome; SPAWN_SAMPLES + 1]> {
+        let mut out = array_vec![];
+        let empty = empty_cells(self);
+        let empty_count = empty.count_ones();
+        let food_count = self.board.food.len();
+        let minimum = self.rules.minimum_food as usize;
+
+        // below the minimum food spawns for sure, otherwise it rolls (100 - rand(100)) < chance
+        let (needed, chance) = if food_count < minimum {
+            (minimum - food_count, 100)
+        } else {
+            (1, self.rules.food_spawn_chance.saturating_sub(1) as u32)
+        };
+        let needed = needed.min(SPAWN_MAX).min(empty_count as usize);
+        if needed == 0 || chance == 0 {
+            out.push(SpawnOutcome {
+                food: array_vec![],
+                weight: 1,
+            });
+            return out;
+        }
+
+        let samples = if needed == 1 && empty_count as usize <= SPAWN_SAMPLES {
+            // few enough cells to try every one of them
+            for n in 0..empty_count {
+                out.push(SpawnOutcome {
+                    food: array_vec![[Coordinate; SPAWN_MAX] => Coordinate::from(nth_bit(empty, n))],
+                    weight: chance,
+                });
+            }
+            empty_count
+        } else {
+            let mut seed = self.board.food_bb as u64 ^ (self.board.food_bb >> 64) as u64;
+            for snake in &self.board.snakes {
+                seed ^= snake.head_bb as u64 ^ (snake.head_bb >> 64) as u64;
+            }
+            seed ^= self.turn as u64;
+            seed |= 1;
+            for _ in 0..SPAWN_SAMPLES {
+                let mut left = empty;
+                let mut outcome = SpawnOutcome {
+                    food: array_vec![],
+                    weight: chance,
+                };
+                for _ in 0..needed {
+                    let cell = nth_bit(
+                        left,
+                        (next_random(&mut seed) % left.count_ones() as u64) as u32,
+                    );
+                    left &= !cell;
+                    outcome.food.push(Coordinate::from(cell));
+                }
+                out.push(outcome);
+            }
+            SPAWN_SAMPLES as u32
+        };
+
+        if chance < 100 {
+            // every sample gets chance, so nothing spawning gets the rest scaled up the same way
+            out.push(SpawnOutcome {
+                food: array_vec![],
+                weight: (100 - chance) * samples,
+            });
+        }
+        out
+    }
+
+    fn spawn_food(&mut self, food: &[Coordinate]) {
+        for cell in food {
+            self.board.food.push(*cell);
+            self.board.food_bb |= u128::from(*cell);
+        }
+    }
+
+    fn unspawn_food(&mut self, food: &[Coordinate]) {
+        for cell in food {
+            if let Some(index) = self.board.food.iter().position(|x| x == cell) {
+                self.board.food.swap_remove(index);
+            }
+            self.board.food_bb &= !u128::from(*cell);
+        }
+    }
+}
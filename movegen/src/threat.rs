@@ -0,0 +1,105 @@
+use board::{
+    board::Coordinate,
+    small::{SmallRequest, SNAKE_MAX},
+};
+
+use crate::{
+    bitmove::{mask_moves, BitMoves},
+    genmove::GenMove,
+};
+
+/// Where every snake's head can be next turn, and who wins where heads can meet
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ThreatMap {
+    /// Cells each snake can move its head into, by id
+    pub reach: [u128; SNAKE_MAX],
+    /// Cells more than one snake can reach
+    pub contested: u128,
+    /// Length of each snake, dead ones are 0
+    lengths: [u16; SNAKE_MAX],
+}
+
+impl ThreatMap {
+    /// Mask of the ids of the snakes that can reach the cell
+    pub fn snakes_at(&self, cell: Coordinate) -> u8 {
+        let bb = u128::from(cell);
+        let mut out = 0;
+        for (id, reach) in self.reach.iter().enumerate() {
+            if reach & bb != 0 {
+                out |= 1 << id;
+            }
+        }
+        out
+    }
+
+    /// The snake that survives if everyone who can reach the cell goes there.
+    /// None when nobody can reach it or the longest ones tie and all die.
+    pub fn winner_at(&self, cell: Coordinate) -> Option<u8> {
+        let snakes = self.snakes_at(cell);
+        let longest = (0..SNAKE_MAX)
+            .filter(|id| snakes & (1 << id) != 0)
+            .map(|id| self.lengths[id])
+            .max()?;
+        let mut winners = (0..SNAKE_MAX as u8)
+            .filter(|id| snakes & (1 << id) != 0 && self.lengths[*id as usize] == longest);
+        let winner = winners.next();
+        match winners.next() {
+            Some(_) => None,
+            None => winner,
+        }
+    }
+
+    /// Cells the snake can reach where an equal or longer snake can meet it head on
+    pub fn danger(&self, id: usize) -> u128 {
+        let mut out = 0;
+        for (other, reach) in self.reach.iter().enumerate() {
+            if other != id && self.lengths[other] >= self.lengths[id] {
+                out |= reach;
+            }
+        }
+        out & self.reach[id]
+    }
+
+    /// Cells the snake can reach where a shorter snake can meet it head on and lose
+    pub fn kills(&self, id: usize) -> u128 {
+        let mut out = 0;
+        for (other, reach) in self.reach.iter().enumerate() {
+            if other != id && self.lengths[other] < self.lengths[id] {
+                out |= reach;
+            }
+        }
+        out & self.reach[id] & !self.danger(id)
+    }
+}
+
+/// Head to head threats for every snake at once
+pub trait Threats {
+    /// Work out where every alive snake can go next turn.
+    /// A snake with no non-fatal move still reaches the cells its doomed moves go to.
+    fn threat_map(&self) -> ThreatMap;
+}
+
+impl Threats for SmallRequest {
+    fn threat_map(&self) -> ThreatMap {
+        let mut out = ThreatMap::default();
+        let masks = self.move_masks();
+        let mut seen = 0;
+        for snake in &self.board.snakes {
+            if !snake.alive {
+                continue;
+            }
+            let id = snake.id as usize;
+            let mut moves = mask_moves(masks[id], snake.id);
+            if moves.is_empty() {
+                moves = self.doomed_moves(id);
+            }
+            for mov in moves {
+                out.reach[id] |= u128::from(snake.head + mov.direction.into());
+            }
+            out.lengths[id] = snake.length;
+            out.contested |= seen & out.reach[id];
+            seen |= out.reach[id];
+        }
+        out
+    }
+}
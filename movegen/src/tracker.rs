@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use board::board::{Coordinate, GameRequest};
+
+use crate::makeunmake::Direction;
+
+/// What one snake did between two turns
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnakeChange {
+    /// Id of the snake in the request
+    pub id: String,
+    /// Where it moved, None if its head didn't move by exactly one cell
+    pub direction: Option<Direction>,
+    /// Whether it ate food on the way
+    pub ate: bool,
+}
+
+/// Everything that happened between two consecutive turns of a game
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TurnChanges {
+    /// Turn the moves were made on
+    pub turn: i64,
+    /// The snakes that are still around and what they did
+    pub moves: Vec<SnakeChange>,
+    /// Ids of the snakes that were eliminated
+    pub died: Vec<String>,
+    /// Food that was there before and is gone now
+    pub eaten_food: Vec<Coordinate>,
+    /// Food that wasn't there before
+    pub spawned_food: Vec<Coordinate>,
+}
+
+/// The direction that takes a head from one cell to the next, if they are next to each other
+pub fn direction_between(from: Coordinate, to: Coordinate) -> Option<Direction> {
+    [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ]
+    .into_iter()
+    .find(|dir| from + Coordinate::from(*dir) == to)
+}
+
+/// Work out what happened between two requests of a game.
+/// None unless next is the turn straight after prev, since moves can't be told apart over a gap.
+pub fn turn_changes(prev: &GameRequest, next: &GameRequest) -> Option<TurnChanges> {
+    if next.turn != prev.turn + 1 {
+        return None;
+    }
+    let prev_small = prev.into_small();
+    let next_small = next.into_small();
+    let next_ids = next.snake_ids();
+    let mut out = TurnChanges {
+        turn: prev.turn,
+        moves: vec![],
+        died: vec![],
+        eaten_food: vec![],
+        spawned_food: vec![],
+    };
+    for (id, name) in prev.snake_ids().into_iter().enumerate() {
+        match next_ids.iter().position(|x| *x == name) {
+            Some(next_id) => {
+                let head = next_small.board.snakes[next_id].head;
+                out.moves.push(SnakeChange {
+                    id: name.to_string(),
+                    direction: direction_between(prev_small.board.snakes[id].head, head),
+                    ate: prev_small.board.food.contains(&head),
+                })
+            }
+            None => out.died.push(name.to_string()),
+        }
+    }
+    out.eaten_food = prev_small
+        .board
+        .food
+        .iter()
+        .filter(|x| !next_small.board.food.contains(x))
+        .copied()
+        .collect();
+    out.spawned_food = next_small
+        .board
+        .food
+        .iter()
+        .filter(|x| !prev_small.board.food.contains(x))
+        .copied()
+        .collect();
+    Some(out)
+}
+
+/// Keeps the last request of every game, so each new one can be compared against it
+#[derive(Clone, Debug, Default)]
+pub struct Tracker {
+    /// Last request seen, by game id
+    previous: HashMap<String, GameRequest>,
+}
+
+impl Tracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remember the request and give what changed since the last one of its game.
+    /// The first request of a game, a missed turn or a repeated one gives None and starts over from this request.
+    pub fn observe(&mut self, request: &GameRequest) -> Option<TurnChanges> {
+        let changes = self
+            .previous
+            .get(request.game_id())
+            .and_then(|prev| turn_changes(prev, request));
+        self.previous
+            .insert(request.game_id().to_string(), request.clone());
+        changes
+    }
+
+    /// Drop a game that has ended
+    pub fn forget(&mut self, game_id: &str) {
+        self.previous.remove(game_id);
+    }
+}
@@ -1,2 +1,189 @@
 pub mod search;
+#[allow(dead_code)]
 mod table;
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use board::board::GameRequest;
+    use board::notation::Notation;
+    use board::rules::GameMode;
+    use board::small::SmallRequest;
+    use evaluation::evaluator::{Evaluator, StandardEval};
+    use movegen::genmove::GenMove;
+    use movegen::makeunmake::Direction;
+
+    use crate::search::Search;
+
+    fn fixture(name: &str) -> serde_json::Value {
+        let contents = fs::read_to_string(
+            env!("CARGO_MANIFEST_DIR").to_string() + "/../movegen/tests/" + name + ".json",
+        )
+        .expect("Something went wrong reading the file");
+        serde_json::from_str(&contents).expect("Invalid json")
+    }
+
+    #[test]
+    fn solo_detection_test() {
+        let mut json = fixture("food");
+        let request: GameRequest = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(request.into_small().rules.mode, GameMode::Standard);
+        json["game"]["ruleset"]["name"] = "solo".into();
+        let request: GameRequest = serde_json::from_value(json).unwrap();
+        assert_eq!(request.into_small().rules.mode, GameMode::Solo);
+    }
+
+    #[test]
+    fn solo_survival_test() {
+        let evaluator = StandardEval::default();
+        // alone on the board isn't a win in solo
+        let mut small = SmallRequest::from_notation("11x11;r solo;y 100 5,5 5,4 5,3").unwrap();
+        let eval = small.minimax(&evaluator, 2, i32::MIN, i32::MAX, true, None);
+        assert!(eval.score < i32::MAX - 1000);
+        assert!(eval.direction.is_some());
+
+        // one health left, going right starves now, eating on the left is boxed in a turn later
+        let mut small =
+            SmallRequest::from_notation("11x11;r solo;f 0,0;y 1 1,0 1,1 0,1 0,2 0,3").unwrap();
+        let eval = small.minimax(&evaluator, 3, i32::MIN, i32::MAX, true, None);
+        assert_eq!(eval.direction, Some(Direction::Left));
+        assert!(eval.score < i32::MIN + 1000);
+    }
+
+    #[test]
+    fn search_leaves_test() {
+        // with food spawning everywhere these took hundreds of thousands of leaves, if they grow again it should be on purpose
+        for (name, most) in [("food", 30000), ("head", 7000), ("movegen", 9000)] {
+            let request: GameRequest = serde_json::from_value(fixture(name)).unwrap();
+            let mut small = request.into_small();
+            let standard = StandardEval::default();
+            let leaves = AtomicU64::new(0);
+            let counted = |state: &SmallRequest| {
+                leaves.fetch_add(1, Ordering::Relaxed);
+                standard.eval(state)
+            };
+            let eval = small.minimax(&counted, 5, i32::MIN, i32::MAX, true, None);
+            assert!(eval.direction.is_some(), "{}", name);
+            let leaves = leaves.load(Ordering::Relaxed);
+            assert!(leaves <= most, "{} took {} leaves", name, leaves);
+        }
+    }
+
+    #[test]
+    fn iterative_deepening_test() {
+        let request: GameRequest = serde_json::from_value(fixture("food")).unwrap();
+        let mut small = request.into_small();
+        let evaluator = StandardEval::default();
+        // no time at all still gives the first depth
+        let (eval, depth) = small.iterative_deepening(&evaluator, 10, Duration::ZERO);
+        assert_eq!(depth, 1);
+        assert!(eval.direction.is_some());
+        // plenty of time stops at the deepest allowed, with the same answer as searching it straight away
+        let (eval, depth) = small.iterative_deepening(&evaluator, 3, Duration::from_secs(600));
+        assert_eq!(depth, 3);
+        let direct = small.minimax(&evaluator, 3, i32::MIN, i32::MAX, true, None);
+        assert_eq!(eval.score, direct.score);
+        assert_eq!(eval.direction, direct.direction);
+    }
+
+    #[test]
+    fn iterative_deepening_deadline_test() {
+        let request: GameRequest = serde_json::from_value(fixture("four_player")).unwrap();
+        let mut small = request.into_small();
+        let before = small.clone();
+        let standard = StandardEval::default();
+        let leaves = AtomicU64::new(0);
+        let counted = |state: &SmallRequest| {
+            leaves.fetch_add(1, Ordering::Relaxed);
+            standard.eval(state)
+        };
+        let depth_one = small.minimax(&counted, 1, i32::MIN, i32::MAX, true, None);
+        let first = leaves.swap(0, Ordering::Relaxed);
+        // past the first depth every leaf suddenly takes long, far more than the growth estimate planned for
+        let slow = |state: &SmallRequest| {
+            if leaves.fetch_add(1, Ordering::Relaxed) >= first {
+                thread::sleep(Duration::from_millis(20));
+            }
+            standard.eval(state)
+        };
+        let start = Instant::now();
+        let (eval, depth) = small.iterative_deepening(&slow, 10, Duration::from_millis(100));
+        assert!(
+            start.elapsed() < Duration::from_millis(500),
+            "took {:?}",
+            start.elapsed()
+        );
+        // the abandoned second depth is thrown away, and its moves are unmade
+        assert_eq!(depth, 1);
+        assert_eq!(eval.score, depth_one.score);
+        assert_eq!(eval.direction, depth_one.direction);
+        assert_eq!(small.board.snakes, before.board.snakes);
+        assert_eq!(small.board.food, before.board.food);
+    }
+
+    /// Only looks at your own snake, so where far away snakes go doesn't matter to it
+    struct Local {
+        prune: bool,
+    }
+
+    impl Evaluator for Local {
+        fn eval(&self, state: &SmallRequest) -> i32 {
+            let you = &state.board.snakes[state.you];
+            you.length as i32 * 100 + you.health as i32 + state.snake_moves(state.you).len() as i32
+        }
+
+        fn prune_distant(&self) -> bool {
+            self.prune
+        }
+    }
+
+    #[test]
+    fn prune_distant_test() {
+        assert!(StandardEval::default().prune_distant());
+        let mut positions = vec![];
+        for name in ["four_player", "food", "movegen"] {
+            let request: GameRequest = serde_json::from_value(fixture(name)).unwrap();
+            positions.push((name, request.into_small()));
+        }
+        // snake 2 is far from you, but meeting snake 1 head to head clears the body next to your head
+        positions.push((
+            "head to head",
+            SmallRequest::from_notation(
+                "11x11;y 100 5,0 4,0 3,0;s 100 8,2 7,2 6,2 5,2 4,2 3,2;s 100 10,2 10,3 9,3 8,3 7,3 6,3 5,3",
+            )
+            .unwrap(),
+        ));
+        for (name, mut small) in positions {
+            // sampled spawns are picked among the empty cells, so they see every snake, leave them out
+            small.rules.food_spawn_chance = 0;
+            small.rules.minimum_food = 0;
+            for depth in 1..=4 {
+                let pruned = small.minimax(
+                    &Local { prune: true },
+                    depth,
+                    i32::MIN,
+                    i32::MAX,
+                    true,
+                    None,
+                );
+                let full = small.minimax(
+                    &Local { prune: false },
+                    depth,
+                    i32::MIN,
+                    i32::MAX,
+                    true,
+                    None,
+                );
+                assert_eq!(pruned.score, full.score, "{} at depth {}", name, depth);
+                assert_eq!(
+                    pruned.direction, full.direction,
+                    "{} at depth {}",
+                    name, depth
+                );
+            }
+        }
+    }
+}
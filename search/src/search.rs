@@ -1,10 +1,21 @@
-use board::small::SmallRequest;
-use evaluation::eval::StaticEval;
+use std::time::{Duration, Instant};
+
+use board::{rules::GameMode, small::SmallRequest};
+use evaluation::evaluator::Evaluator;
 use movegen::{
     genmove::GenMove,
     makeunmake::{Direction, MakeUnmake, Move},
+    spawn::SpawnFood,
+    threat::Threats,
 };
 
+/// Plies below the root that food spawning is modeled for, further down the food stays as it is.
+/// Every chance node multiplies the tree by its outcomes and searches them without bounds, so only the nearest spawns are worth it.
+pub const SPAWN_PLIES: u8 = 1;
+
+/// Least amount of times longer the next depth of iterative deepening is expected to take
+const MIN_GROWTH: u32 = 4;
+
 pub struct Evaluation {
     pub score: i32,
     pub direction: Option<Direction>,
@@ -13,98 +24,290 @@ pub struct Evaluation {
 pub trait Search {
     fn minimax(
         &mut self,
+        evaluator: &dyn Evaluator,
         depth: u8,
         alpha: i32,
         beta: i32,
         maximizing: bool,
         you_move: Option<Move>,
     ) -> Evaluation;
+    /// Search one ply deeper at a time, up to max_depth, while the next depth is expected to finish within the budget.
+    /// A depth still running once the budget is up is abandoned, depth 1 always finishes.
+    /// Gives the result of the deepest search that finished along with its depth.
+    fn iterative_deepening(
+        &mut self,
+        evaluator: &dyn Evaluator,
+        max_depth: u8,
+        budget: Duration,
+    ) -> (Evaluation, u8);
 }
 
 impl Search for SmallRequest {
     fn minimax(
         &mut self,
+        evaluator: &dyn Evaluator,
         depth: u8,
-        mut alpha: i32,
-        mut beta: i32,
+        alpha: i32,
+        beta: i32,
         maximizing: bool,
         you_move: Option<Move>,
     ) -> Evaluation {
-        if self.amount_alive() as usize == 0 {
-            // noone is alive
-            return Evaluation {
-                score: 0,
-                direction: None,
-            };
-        } else if !self.board.snakes[self.you].alive {
-            // im not alive
-            return Evaluation {
-                score: i32::MIN + 1000,
-                direction: None,
-            };
-        } else if self.amount_alive() as usize == 1 {
-            // im the only one left
+        let you_move = if maximizing { None } else { you_move };
+        node(self, evaluator, depth, 0, alpha, beta, you_move, None)
+    }
+
+    fn iterative_deepening(
+        &mut self,
+        evaluator: &dyn Evaluator,
+        max_depth: u8,
+        budget: Duration,
+    ) -> (Evaluation, u8) {
+        let start = Instant::now();
+        let deadline = Some(start + budget);
+        let mut out = self.minimax(evaluator, 1, i32::MIN, i32::MAX, true, None);
+        let mut last = start.elapsed();
+        let mut previous = Duration::ZERO;
+        for depth in 2..=max_depth {
+            // expect the next depth to grow by as much as the last one did, and at least by MIN_GROWTH
+            let mut growth = MIN_GROWTH;
+            if !previous.is_zero() {
+                growth = growth.max((last.as_nanos() / previous.as_nanos()) as u32);
+            }
+            if start.elapsed() + last * growth > budget {
+                return (out, depth - 1);
+            }
+            let iteration = Instant::now();
+            let eval = node(
+                self,
+                evaluator,
+                depth,
+                0,
+                i32::MIN,
+                i32::MAX,
+                None,
+                deadline,
+            );
+            // an abandoned depth scored only part of the tree
+            if out_of_time(deadline) {
+                return (out, depth - 1);
+            }
+            out = eval;
+            previous = last;
+            last = iteration.elapsed();
+        }
+        (out, max_depth)
+    }
+}
+
+/// Whether the search is past its deadline, if it has one
+fn out_of_time(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|x| Instant::now() >= x)
+}
+
+/// A node ply joint moves below the root.
+/// You pick a move when you_move is None, otherwise the others answer it.
+/// Past the deadline it gives up with a meaningless score, the caller has to throw the result away.
+#[allow(clippy::too_many_arguments)]
+fn node(
+    state: &mut SmallRequest,
+    evaluator: &dyn Evaluator,
+    depth: u8,
+    ply: u8,
+    mut alpha: i32,
+    mut beta: i32,
+    you_move: Option<Move>,
+    deadline: Option<Instant>,
+) -> Evaluation {
+    if out_of_time(deadline) {
+        return Evaluation {
+            score: 0,
+            direction: None,
+        };
+    }
+    if state.rules.mode == GameMode::Solo {
+        // solo games are about lasting as long as possible, so being the only one alive is not a win
+        if !state.board.snakes[state.you].alive {
+            // dying later is better than dying sooner
             return Evaluation {
-                score: i32::MAX - 1000,
+                score: i32::MIN + 1000 - depth as i32,
                 direction: None,
             };
         }
+    } else if state.amount_alive() as usize == 0 {
+        // noone is alive
+        return Evaluation {
+            score: 0,
+            direction: None,
+        };
+    } else if !state.board.snakes[state.you].alive {
+        // im not alive
+        return Evaluation {
+            score: i32::MIN + 1000,
+            direction: None,
+        };
+    } else if state.amount_alive() as usize == 1 {
+        // im the only one left
+        return Evaluation {
+            score: i32::MAX - 1000,
+            direction: None,
+        };
+    }
+
+    if depth == 0 {
+        let score = evaluator.eval(state);
+        return Evaluation {
+            score,
+            direction: None,
+        };
+    }
 
-        if depth == 0 {
+    let maximizing = you_move.is_none();
+    if maximizing {
+        let mut value = i32::MIN;
+        let mut out = None;
+        if state.snake_moves(state.you).is_empty() {
+            // If you don't have any moves, return up with i32::MIN, since you are basically dead
+            let score = if state.rules.mode == GameMode::Solo {
+                // in solo you die on this move, so score it like being dead one ply down
+                i32::MIN + 1000 - (depth as i32 - 1)
+            } else {
+                i32::MIN
+            };
             return Evaluation {
-                score: self.static_eval(),
-                direction: None,
+                score,
+                direction: Some(Direction::Up),
             };
         }
-
-        if maximizing {
-            let mut value = i32::MIN;
-            let mut out = None;
-            if self.snake_moves(self.you).is_empty() {
-                // If you don't have any moves, return up with i32::MIN, since you are basically dead
-                return Evaluation {
-                    score: i32::MIN,
-                    direction: Some(Direction::Up),
-                };
+        // moves that win a head to head first, ones that can lose one last, ties go to the earlier move
+        let threats = state.threat_map();
+        let you_head = state.board.snakes[state.you].head;
+        let mut you_moves = state.snake_moves(state.you);
+        you_moves.sort_by_key(|x| {
+            let to = u128::from(you_head + x.direction.into());
+            if threats.kills(state.you) & to != 0 {
+                0
+            } else if threats.danger(state.you) & to != 0 {
+                2
+            } else {
+                1
             }
-            for current_move in self.snake_moves(self.you) {
-                let eval = self.minimax(depth, alpha, beta, !maximizing, Some(current_move));
-                if value <= eval.score {
-                    out = Some(current_move.direction);
-                    value = eval.score;
-                }
-                if value >= beta {
-                    break;
-                }
-                alpha = alpha.max(value);
+        });
+        for current_move in you_moves {
+            let eval = node(
+                state,
+                evaluator,
+                depth,
+                ply,
+                alpha,
+                beta,
+                Some(current_move),
+                deadline,
+            );
+            if out.is_none() || value < eval.score {
+                out = Some(current_move.direction);
+                value = eval.score;
             }
-            Evaluation {
-                score: value,
-                direction: out,
+            if value >= beta || out_of_time(deadline) {
+                break;
             }
-        } else {
-            // let mut best_moves = vec![];
-            let mut value = i32::MAX;
+            alpha = alpha.max(value);
+        }
+        Evaluation {
+            score: value,
+            direction: out,
+        }
+    } else {
+        // let mut best_moves = vec![];
+        let mut value = i32::MAX;
 
-            for moves in &self.all_snake_moves(you_move.unwrap()) {
-                let delta = self.make_move(moves);
+        // opponents heading straight for you are the most likely to cause a cutoff
+        let you_head = state.board.snakes[state.you].head;
+        let snakes = &state.board.snakes;
+        // far away snakes get fewer moves, unless the evaluator needs them all
+        let near = if evaluator.prune_distant() {
+            depth
+        } else {
+            u8::MAX
+        };
+        let joint_moves = state
+            .joint_moves_near(you_move.unwrap(), near)
+            .ordered_by(|x| {
+                let to = snakes[x.id as usize].head + x.direction.into();
+                (to.x - you_head.x).abs() + (to.y - you_head.y).abs()
+            });
+        for moves in joint_moves {
+            let delta = state.make_move(&moves);
 
-                let eval = self.minimax(depth - 1, alpha, beta, !maximizing, None);
-                self.unmake_move(&delta);
+            let score = expect_food(state, evaluator, depth - 1, ply + 1, alpha, beta, deadline);
+            state.unmake_move(&delta);
 
-                if value >= eval.score {
-                    // best_moves = moves.clone();
-                    value = eval.score;
-                }
-                if value <= alpha {
-                    break;
-                }
-                beta = beta.min(value);
+            if value >= score {
+                // best_moves = moves.clone();
+                value = score;
             }
-            Evaluation {
-                score: value,
-                direction: None,
+            if value <= alpha || out_of_time(deadline) {
+                break;
             }
+            beta = beta.min(value);
+        }
+        Evaluation {
+            score: value,
+            direction: None,
+        }
+    }
+}
+
+/// Chance node between turns, ply joint moves below the root.
+/// Averages the score over the ways food could spawn, as long as it is within SPAWN_PLIES of the root.
+fn expect_food(
+    state: &mut SmallRequest,
+    evaluator: &dyn Evaluator,
+    depth: u8,
+    ply: u8,
+    alpha: i32,
+    beta: i32,
+    deadline: Option<Instant>,
+) -> i32 {
+    // food spawning at the leaf can't be reached anymore, and deep down it isn't worth the time
+    if depth == 0 || ply > SPAWN_PLIES {
+        return node(state, evaluator, depth, ply, alpha, beta, None, deadline).score;
+    }
+    let outcomes = state.spawn_outcomes();
+    if outcomes.len() == 1 && outcomes[0].food.is_empty() {
+        return node(state, evaluator, depth, ply, alpha, beta, None, deadline).score;
+    }
+    // Star1: the scores seen so far and the widest the rest can go put a window on each outcome,
+    // the last and heaviest one, nothing spawning, ends up with a narrow one
+    let total_weight: i64 = outcomes.iter().map(|x| x.weight as i64).sum();
+    let mut left = total_weight;
+    let mut total = 0i64;
+    for outcome in &outcomes {
+        let weight = outcome.weight as i64;
+        left -= weight;
+        let low = (alpha as i64 * total_weight - total - i32::MAX as i64 * left).div_euclid(weight);
+        let high =
+            -(-(beta as i64 * total_weight - total - i32::MIN as i64 * left)).div_euclid(weight);
+        state.spawn_food(&outcome.food);
+        let eval = node(
+            state,
+            evaluator,
+            depth,
+            ply,
+            low.max(i32::MIN as i64) as i32,
+            high.min(i32::MAX as i64) as i32,
+            None,
+            deadline,
+        );
+        state.unspawn_food(&outcome.food);
+        let score = eval.score as i64;
+        // whatever the rest score, the average can't get back into the window
+        if score <= low {
+            return alpha;
+        }
+        if score >= high {
+            return beta;
         }
+        total += score * weight;
     }
+    (total / total_weight) as i32
 }
@@ -1,7 +1,7 @@
 use std::ops::{Index, IndexMut};
 
 use board::small::SmallBoard;
-use movegen::makeunmake::Move;
+use movegen::packed::PackedMoves;
 const TT_SIZE: u64 = 1000000;
 
 #[derive(Clone, PartialEq, Eq)]
@@ -15,7 +15,7 @@ enum Flag {
 #[derive(Clone)]
 struct Data {
     score: i32,
-    best_move: Vec<Move>,
+    best_move: PackedMoves,
     flag: Flag,
     depth: u8,
 }
@@ -24,7 +24,7 @@ impl Data {
     fn new() -> Self {
         Self {
             score: 0,
-            best_move: vec![],
+            best_move: PackedMoves::new(),
             flag: Flag::Nothing,
             depth: 0,
         }